@@ -0,0 +1,68 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+/// Source of the current date, so that date-relative behavior can be pinned
+/// in tests.
+pub trait Clock {
+    fn today(&self) -> NaiveDate;
+}
+
+/// The local date of the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        chrono::Local::now().date_naive()
+    }
+}
+
+/// The date in a timezone, whatever the system timezone is. Daylight saving
+/// time is taken into account.
+pub struct TimezoneClock(pub Tz);
+
+impl TimezoneClock {
+    fn date_at(&self, now: DateTime<Utc>) -> NaiveDate {
+        now.with_timezone(&self.0).date_naive()
+    }
+}
+
+impl Clock for TimezoneClock {
+    fn today(&self) -> NaiveDate {
+        self.date_at(Utc::now())
+    }
+}
+
+/// Always returns the same date.
+pub struct FixedClock(pub NaiveDate);
+
+impl Clock for FixedClock {
+    fn today(&self) -> NaiveDate {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timezone_clock_follows_daylight_saving_time() {
+        let new_york = TimezoneClock(chrono_tz::America::New_York);
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        // 03:30 UTC is still the previous evening under EDT (UTC-4)
+        assert_eq!(
+            new_york.date_at(at("2024-07-01T03:30:00Z")),
+            NaiveDate::from_ymd_opt(2024, 6, 30).unwrap()
+        );
+        // 04:30 UTC is the previous evening under EST (UTC-5), but would be
+        // the next day at a fixed UTC-4 offset
+        assert_eq!(
+            new_york.date_at(at("2024-01-01T04:30:00Z")),
+            NaiveDate::from_ymd_opt(2023, 12, 31).unwrap()
+        );
+        assert_eq!(
+            new_york.date_at(at("2024-07-01T04:30:00Z")),
+            NaiveDate::from_ymd_opt(2024, 7, 1).unwrap()
+        );
+    }
+}
@@ -1,10 +1,35 @@
 use crate::number_formatter::{CurrencyPosition, FormatOptions};
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Default, Deserialize, Eq, PartialEq)]
 #[serde(default)]
 pub struct Config {
     pub formatting: FormattingConfig,
+    /// Maps a file name (e.g. `expenses.csv`) to the kind of account it tracks.
+    pub accounts: HashMap<String, AccountKind>,
+    pub keys: KeysConfig,
+    pub tui: TuiConfig,
+    pub layout: LayoutConfig,
+    pub backup: BackupConfig,
+    /// What a report does when no entries match.
+    pub empty_result: EmptyResult,
+}
+
+#[derive(Debug, Copy, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyResult {
+    /// Fail with a non-zero exit code.
+    #[default]
+    Error,
+    /// Print the empty report with a zero total.
+    Ok,
+}
+
+impl Config {
+    pub fn account_kind(&self, file_name: &str) -> Option<AccountKind> {
+        self.accounts.get(file_name).copied()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
@@ -18,9 +43,29 @@ pub struct FormattingConfig {
     pub thousands_separator: char,
     #[serde(rename = "decimal_separator")]
     pub decimal_separator: char,
+    /// Zero-pads the integer part of amounts to this many digits.
+    pub pad_integer_digits: Option<usize>,
+    /// Rounds report rows so that they add up to the shown total.
+    pub reconcile_rounding: bool,
 }
 
 impl FormattingConfig {
+    fn has_distinct_separators(&self) -> bool {
+        self.thousands_separator != self.decimal_separator
+    }
+
+    /// Returns a warning if the separators can't be told apart.
+    pub fn warning(&self) -> Option<String> {
+        (!self.has_distinct_separators()).then(|| {
+            format!(
+                "Thousands and decimal separators are both {:?}, using the default separators",
+                self.decimal_separator
+            )
+        })
+    }
+
+    /// The options to format amounts with, with the default separators if
+    /// the configured ones are the same.
     pub fn format_options(&self) -> FormatOptions {
         let currency = match (self.currency.as_ref(), self.currency_position) {
             (Some(symbol), Some(CurrencyPositionChoice::Prefix)) => {
@@ -32,14 +77,39 @@ impl FormattingConfig {
             _ => CurrencyPosition::None,
         };
 
+        let (thousands_separator, decimal_separator) = if self.has_distinct_separators() {
+            (self.thousands_separator, self.decimal_separator)
+        } else {
+            let default = Self::default();
+            (default.thousands_separator, default.decimal_separator)
+        };
         FormatOptions {
-            thousands_separator: self.thousands_separator,
-            decimal_separator: self.decimal_separator,
+            thousands_separator,
+            decimal_separator,
             currency,
+            pad_integer_digits: self.pad_integer_digits,
         }
     }
 }
 
+/// Thousands and decimal separators of a POSIX locale name such as
+/// `de_DE.UTF-8`, for locales with well-known conventions.
+pub fn locale_separators(locale: &str) -> Option<(char, char)> {
+    let name = locale.split(['.', '@']).next().unwrap_or_default();
+    let language = name.split('_').next().unwrap_or_default();
+    match (name, language) {
+        ("de_CH", _) => Some(('\'', '.')),
+        (_, "en" | "he" | "ja" | "ko" | "th" | "zh") => Some((',', '.')),
+        (_, "da" | "de" | "el" | "es" | "id" | "it" | "nl" | "pt" | "tr") => Some(('.', ',')),
+        (
+            _,
+            "bg" | "cs" | "et" | "fi" | "fr" | "hu" | "lt" | "lv" | "nb" | "nn" | "no" | "pl"
+            | "ru" | "sk" | "sv" | "uk",
+        ) => Some(('\u{a0}', ',')),
+        _ => None,
+    }
+}
+
 impl Default for FormattingConfig {
     fn default() -> Self {
         Self {
@@ -47,6 +117,8 @@ impl Default for FormattingConfig {
             currency_position: None,
             thousands_separator: '\u{a0}',
             decimal_separator: '.',
+            pad_integer_digits: None,
+            reconcile_rounding: false,
         }
     }
 }
@@ -58,6 +130,285 @@ pub enum CurrencyPositionChoice {
     Suffix,
 }
 
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct TuiConfig {
+    /// Shows the data directory in a title line above the panes.
+    pub show_directory: bool,
+    /// Shades every other row of the entries pane.
+    pub zebra_stripes: bool,
+    /// Starts with entry amounts shown without their sign.
+    pub absolute_amounts: bool,
+    /// Shows the fractional part of amounts dimmed.
+    pub dim_fractions: bool,
+    /// Where the entries of the selected year are shown.
+    pub view: TuiView,
+}
+
+#[derive(Debug, Copy, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TuiView {
+    /// In a pane of their own next to the years.
+    #[default]
+    Panes,
+    /// Inline under their year in the years pane, expanded with Enter.
+    Tree,
+}
+
+/// Relative widths of the TUI panes, e.g. `entries = 2` makes the entries
+/// pane twice as wide as each of the others.
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub files: u32,
+    pub years: u32,
+    pub entries: u32,
+}
+
+impl LayoutConfig {
+    const MAX_RATIO: u32 = 100;
+
+    fn is_valid(&self) -> bool {
+        [self.files, self.years, self.entries]
+            .iter()
+            .all(|ratio| (1..=Self::MAX_RATIO).contains(ratio))
+    }
+
+    /// Returns a warning if the ratios can't be used.
+    pub fn warning(&self) -> Option<String> {
+        (!self.is_valid()).then(|| {
+            format!(
+                "Layout ratios must be between 1 and {}, using equal pane widths",
+                Self::MAX_RATIO
+            )
+        })
+    }
+
+    /// The ratios of the files, years and entries panes, equal ones if the
+    /// configured ratios can't be used.
+    pub fn ratios(&self) -> [u32; 3] {
+        if self.is_valid() {
+            [self.files, self.years, self.entries]
+        } else {
+            [1; 3]
+        }
+    }
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            files: 1,
+            years: 1,
+            entries: 1,
+        }
+    }
+}
+
+/// Copies of the files that commands and the TUI rewrite, see
+/// [`crate::backup_file`].
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Backs up a file before changing it, also turned on by `--backup`.
+    pub enabled: bool,
+    /// Replaces an existing backup instead of adding a numbered one.
+    pub overwrite: bool,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            overwrite: true,
+        }
+    }
+}
+
+/// Character keys of the TUI actions. Arrow keys and Tab always keep working.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct KeysConfig {
+    pub next: char,
+    pub prev: char,
+    /// An extra key to cycle focus, in addition to Tab.
+    pub focus_next: Option<char>,
+    pub add: char,
+    pub edit: char,
+    /// Shows the details of the selected entry.
+    pub info: char,
+    pub view: char,
+    /// Toggles between signed and absolute entry amounts.
+    pub absolute: char,
+    /// Opens a popup to jump to the first entry on or after a date.
+    pub goto: char,
+    pub quit: char,
+}
+
+impl KeysConfig {
+    /// Returns a warning for every key bound to more than one action.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut actions_by_key: Vec<(char, Vec<&str>)> = Vec::new();
+        let bindings = [
+            ("next", Some(self.next)),
+            ("prev", Some(self.prev)),
+            ("focus_next", self.focus_next),
+            ("add", Some(self.add)),
+            ("edit", Some(self.edit)),
+            ("info", Some(self.info)),
+            ("view", Some(self.view)),
+            ("absolute", Some(self.absolute)),
+            ("goto", Some(self.goto)),
+            ("quit", Some(self.quit)),
+        ];
+        for (action, key) in bindings {
+            let Some(key) = key else { continue };
+            match actions_by_key.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, actions)) => actions.push(action),
+                None => actions_by_key.push((key, vec![action])),
+            }
+        }
+        actions_by_key
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|(key, actions)| {
+                format!(
+                    "Key '{key}' is bound to multiple actions: {}",
+                    actions.join(", ")
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        Self {
+            next: 'j',
+            prev: 'k',
+            focus_next: None,
+            add: 'n',
+            edit: 'e',
+            info: 'i',
+            view: 'v',
+            absolute: 'x',
+            goto: 'g',
+            quit: 'q',
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountKind {
+    Expense,
+    Income,
+}
+
+/// Quotes a TOML basic string, escaping control and non-space whitespace
+/// characters like the default non-breaking thousands separator.
+fn toml_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            ch if ch.is_control() || (ch.is_whitespace() && ch != ' ') => {
+                quoted.push_str(&format!("\\u{:04X}", ch as u32));
+            }
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Prints the effective settings in a TOML-like layout. Strings are quoted and
+/// escaped so that invisible separators (e.g. a non-breaking space) show up.
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let empty_result = match self.empty_result {
+            EmptyResult::Error => "error",
+            EmptyResult::Ok => "ok",
+        };
+        writeln!(f, "empty_result = \"{empty_result}\"")?;
+
+        let formatting = &self.formatting;
+        writeln!(f, "\n[formatting]")?;
+        match &formatting.currency {
+            Some(symbol) => writeln!(f, "currency_symbol = {}", toml_string(symbol))?,
+            None => writeln!(f, "# currency_symbol is not set")?,
+        }
+        match formatting.currency_position {
+            Some(position) => writeln!(f, "currency_position = \"{position:?}\"")?,
+            None => writeln!(f, "# currency_position is not set")?,
+        }
+        writeln!(
+            f,
+            "thousands_separator = {}",
+            toml_string(&formatting.thousands_separator.to_string())
+        )?;
+        writeln!(
+            f,
+            "decimal_separator = {}",
+            toml_string(&formatting.decimal_separator.to_string())
+        )?;
+        match formatting.pad_integer_digits {
+            Some(digits) => writeln!(f, "pad_integer_digits = {digits}")?,
+            None => writeln!(f, "# pad_integer_digits is not set")?,
+        }
+        writeln!(f, "reconcile_rounding = {}", formatting.reconcile_rounding)?;
+
+        writeln!(f, "\n[accounts]")?;
+        let mut accounts: Vec<_> = self.accounts.iter().collect();
+        accounts.sort_by_key(|(file_name, _)| *file_name);
+        for (file_name, kind) in accounts {
+            let kind = match kind {
+                AccountKind::Expense => "expense",
+                AccountKind::Income => "income",
+            };
+            writeln!(f, "{} = \"{kind}\"", toml_string(file_name))?;
+        }
+
+        let keys = &self.keys;
+        writeln!(f, "\n[keys]")?;
+        let key = |key: char| toml_string(&key.to_string());
+        writeln!(f, "next = {}", key(keys.next))?;
+        writeln!(f, "prev = {}", key(keys.prev))?;
+        match keys.focus_next {
+            Some(focus_next) => writeln!(f, "focus_next = {}", key(focus_next))?,
+            None => writeln!(f, "# focus_next is not set")?,
+        }
+        writeln!(f, "add = {}", key(keys.add))?;
+        writeln!(f, "edit = {}", key(keys.edit))?;
+        writeln!(f, "info = {}", key(keys.info))?;
+        writeln!(f, "view = {}", key(keys.view))?;
+        writeln!(f, "absolute = {}", key(keys.absolute))?;
+        writeln!(f, "goto = {}", key(keys.goto))?;
+        writeln!(f, "quit = {}", key(keys.quit))?;
+
+        writeln!(f, "\n[tui]")?;
+        writeln!(f, "show_directory = {}", self.tui.show_directory)?;
+        writeln!(f, "zebra_stripes = {}", self.tui.zebra_stripes)?;
+        writeln!(f, "absolute_amounts = {}", self.tui.absolute_amounts)?;
+        writeln!(f, "dim_fractions = {}", self.tui.dim_fractions)?;
+        let view = match self.tui.view {
+            TuiView::Panes => "panes",
+            TuiView::Tree => "tree",
+        };
+        writeln!(f, "view = \"{view}\"")?;
+
+        writeln!(f, "\n[layout]")?;
+        writeln!(f, "files = {}", self.layout.files)?;
+        writeln!(f, "years = {}", self.layout.years)?;
+        writeln!(f, "entries = {}", self.layout.entries)?;
+
+        writeln!(f, "\n[backup]")?;
+        writeln!(f, "enabled = {}", self.backup.enabled)?;
+        writeln!(f, "overwrite = {}", self.backup.overwrite)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,12 +422,16 @@ mod tests {
 
     #[test]
     fn test_format_options_conversion() {
-        let mut config = Config::default();
-        config.formatting = FormattingConfig {
-            currency: Some("$".to_string()),
-            currency_position: Some(CurrencyPositionChoice::Prefix),
-            thousands_separator: '\u{a0}',
-            decimal_separator: ',',
+        let config = Config {
+            formatting: FormattingConfig {
+                currency: Some("$".to_string()),
+                currency_position: Some(CurrencyPositionChoice::Prefix),
+                thousands_separator: '\u{a0}',
+                decimal_separator: ',',
+                pad_integer_digits: Some(4),
+                reconcile_rounding: false,
+            },
+            ..Config::default()
         };
 
         let format_options = config.formatting.format_options();
@@ -85,8 +440,117 @@ mod tests {
             FormatOptions {
                 thousands_separator: '\u{a0}',
                 decimal_separator: ',',
-                currency: CurrencyPosition::Prefix(String::from("$"))
+                currency: CurrencyPosition::Prefix(String::from("$")),
+                pad_integer_digits: Some(4),
             }
         );
     }
+
+    #[test]
+    fn test_locale_separators() {
+        assert_eq!(locale_separators("en_US.UTF-8"), Some((',', '.')));
+        assert_eq!(locale_separators("de_DE.UTF-8"), Some(('.', ',')));
+        assert_eq!(locale_separators("de_CH"), Some(('\'', '.')));
+        assert_eq!(locale_separators("fr_FR@euro"), Some(('\u{a0}', ',')));
+        assert_eq!(locale_separators("C"), None);
+        assert_eq!(locale_separators("POSIX"), None);
+    }
+
+    #[test]
+    fn test_accounts_section() {
+        let config: Config = ::config::Config::builder()
+            .add_source(::config::File::from_str(
+                r#"
+                [accounts]
+                "expenses.csv" = "expense"
+                "income.csv" = "income"
+                "#,
+                ::config::FileFormat::Toml,
+            ))
+            .build()
+            .and_then(|settings| settings.try_deserialize())
+            .expect("valid config");
+
+        assert_eq!(
+            config.account_kind("expenses.csv"),
+            Some(AccountKind::Expense)
+        );
+        assert_eq!(config.account_kind("income.csv"), Some(AccountKind::Income));
+        assert_eq!(config.account_kind("savings.csv"), None);
+    }
+
+    #[test]
+    fn test_default_keys_have_no_conflicts() {
+        assert!(KeysConfig::default().conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_keys_conflicts() {
+        let keys = KeysConfig {
+            next: 'q',
+            focus_next: Some('e'),
+            ..KeysConfig::default()
+        };
+        assert_eq!(
+            keys.conflicts(),
+            vec![
+                String::from("Key 'q' is bound to multiple actions: next, quit"),
+                String::from("Key 'e' is bound to multiple actions: focus_next, edit"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_ratios() {
+        let layout = LayoutConfig {
+            entries: 3,
+            ..LayoutConfig::default()
+        };
+        assert_eq!(layout.warning(), None);
+        assert_eq!(layout.ratios(), [1, 1, 3]);
+
+        let layout = LayoutConfig {
+            files: 0,
+            ..LayoutConfig::default()
+        };
+        assert!(layout.warning().is_some());
+        assert_eq!(layout.ratios(), [1, 1, 1]);
+    }
+
+    #[test]
+    fn test_same_separators_fall_back_to_defaults() {
+        let formatting = FormattingConfig {
+            thousands_separator: '.',
+            decimal_separator: '.',
+            ..FormattingConfig::default()
+        };
+        assert!(formatting.warning().is_some());
+        assert_eq!(
+            formatting.format_options(),
+            FormattingConfig::default().format_options()
+        );
+    }
+
+    #[test]
+    fn test_display_loads_back_as_toml() {
+        let mut config = Config::default();
+        config.formatting.currency = Some(String::from("\"$\""));
+        config.formatting.pad_integer_digits = Some(4);
+        config.keys.focus_next = Some('"');
+        config.keys.goto = '\\';
+        config
+            .accounts
+            .insert(String::from("expenses.csv"), AccountKind::Expense);
+
+        let loaded: Config = ::config::Config::builder()
+            .add_source(::config::File::from_str(
+                &config.to_string(),
+                ::config::FileFormat::Toml,
+            ))
+            .build()
+            .and_then(|settings| settings.try_deserialize())
+            .expect("valid config");
+
+        assert_eq!(loaded, config);
+    }
 }
@@ -1,34 +1,161 @@
+pub mod clock;
 pub mod config;
 pub mod number_formatter;
 pub mod tui;
 
-use chrono::NaiveDate;
+use chrono::{Datelike, Months, NaiveDate};
 use csv::{ReaderBuilder, WriterBuilder};
 use number_formatter::{FormatOptions, NumberFormatter};
-use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::fs::OpenOptions;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 pub const DELIMITER: u8 = b';';
+/// Lines starting with this byte are comments and are skipped when reading.
+pub const COMMENT: u8 = b'#';
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// Entries are equal when the date and both amounts match. They are ordered
+/// by date, then by amount.
+///
+/// The date is validated when the file is read and stored in CSV as an ISO
+/// `YYYY-MM-DD` string.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
 pub struct Entry {
-    pub date: String,
+    pub date: NaiveDate,
     pub amount: Decimal,
+    /// Total of a shared expense, of which `amount` is the own share. Stored
+    /// in an optional `full_amount` column.
+    #[serde(default)]
+    pub full_amount: Option<Decimal>,
 }
 
 impl Entry {
     pub fn day_month_date(&self) -> String {
-        if let Ok(date) = self.date.parse::<NaiveDate>() {
-            date.format("%B %-d").to_string()
+        self.date.format("%B %-d").to_string()
+    }
+}
+
+/// How amounts are stored in a CSV file.
+///
+/// Detected from the header: a file with an `amount_cents` column instead of
+/// `amount` stores integer cents, as some systems export money to avoid
+/// decimals. Amounts are converted to and from `Decimal` on read and write.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum AmountFormat {
+    /// Decimal amounts in an `amount` column
+    #[default]
+    Decimal,
+    /// Integer cents in an `amount_cents` column
+    Cents,
+}
+
+impl AmountFormat {
+    fn from_headers(headers: &csv::StringRecord) -> Self {
+        if headers.iter().any(|header| header == "amount_cents") {
+            AmountFormat::Cents
         } else {
-            self.date.clone()
+            AmountFormat::Decimal
+        }
+    }
+
+    fn headers(self, with_full_amount: bool) -> Vec<&'static str> {
+        let mut headers = match self {
+            AmountFormat::Decimal => vec!["date", "amount", "full_amount"],
+            AmountFormat::Cents => vec!["date", "amount_cents", "full_amount_cents"],
+        };
+        if !with_full_amount {
+            headers.pop();
+        }
+        headers
+    }
+}
+
+/// Line ending of a CSV file, kept when entries are written to it so that
+/// files edited on Windows don't end up with mixed endings. Reading handles
+/// both, a `\r` before the `\n` never ends up in a field.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// The ending of the first line of the file, [`LineEnding::Lf`] for a
+    /// missing or empty file.
+    fn of_file(path: &Path) -> Self {
+        let mut first_line = Vec::new();
+        let read = std::fs::File::open(path).and_then(|file| {
+            std::io::BufRead::read_until(&mut std::io::BufReader::new(file), b'\n', &mut first_line)
+        });
+        match read {
+            Ok(_) if first_line.ends_with(b"\r\n") => LineEnding::CrLf,
+            _ => LineEnding::Lf,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    fn terminator(self) -> csv::Terminator {
+        match self {
+            LineEnding::Lf => csv::Terminator::Any(b'\n'),
+            LineEnding::CrLf => csv::Terminator::CRLF,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CentsEntry {
+    date: NaiveDate,
+    amount_cents: i64,
+    #[serde(default)]
+    full_amount_cents: Option<i64>,
+}
+
+impl From<CentsEntry> for Entry {
+    fn from(entry: CentsEntry) -> Self {
+        Entry {
+            date: entry.date,
+            amount: Decimal::new(entry.amount_cents, 2),
+            full_amount: entry
+                .full_amount_cents
+                .map(|full_amount_cents| Decimal::new(full_amount_cents, 2)),
         }
     }
 }
 
+impl TryFrom<&Entry> for CentsEntry {
+    type Error = AppError;
+
+    fn try_from(entry: &Entry) -> Result<Self, Self::Error> {
+        // Fractions of a cent would be lost, so they fail rather than round
+        let to_cents = |amount: Decimal| {
+            if amount.round_dp(2) != amount {
+                return Err(AppError::SubCentAmount(amount));
+            }
+            amount
+                .checked_mul(Decimal::ONE_HUNDRED)
+                .and_then(|cents| cents.to_i64())
+                .ok_or(AppError::CentsOverflow(amount))
+        };
+        Ok(CentsEntry {
+            date: entry.date,
+            amount_cents: to_cents(entry.amount)?,
+            full_amount_cents: entry.full_amount.map(to_cents).transpose()?,
+        })
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("I/O error: {context}")]
@@ -55,6 +182,255 @@ pub enum AppError {
 
     #[error("No entries matching filter: {0}")]
     FilteredNoEntries(String),
+
+    #[error("No entries after the last marked report: {0}")]
+    NoNewEntries(NaiveDate),
+
+    #[error("Amount doesn't fit into integer cents: {0}")]
+    CentsOverflow(Decimal),
+
+    #[error("Amount has fractions of a cent: {0}")]
+    SubCentAmount(Decimal),
+
+    #[error("Amount {amount} overflows when multiplied by {rate}")]
+    ConversionOverflow { amount: Decimal, rate: Decimal },
+
+    #[error("Invalid amount: {0}")]
+    AmountParse(String),
+
+    #[error("Found {0} invalid rows")]
+    InvalidRows(usize),
+
+    #[error("File is locked by another process: {0}")]
+    Locked(String),
+}
+
+impl AppError {
+    /// Whether the error only says that there was nothing to report.
+    pub fn is_no_entries(&self) -> bool {
+        matches!(
+            self,
+            AppError::NoEntries | AppError::FilteredNoEntries(_) | AppError::NoNewEntries(_)
+        )
+    }
+
+    /// Whether the file was missing or inaccessible, which may only last
+    /// while another process replaces it.
+    fn is_transient(&self) -> bool {
+        let kind = match self {
+            AppError::Io { source, .. } => source.kind(),
+            AppError::Csv { source } => match source.kind() {
+                csv::ErrorKind::Io(source) => source.kind(),
+                _ => return false,
+            },
+            _ => return false,
+        };
+        matches!(
+            kind,
+            std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied
+        )
+    }
+}
+
+/// Parses an amount that may group its digits with underscores or spaces,
+/// e.g. `1_000_000` or `1 000.50`. A separator must sit between two digits.
+pub fn parse_amount(input: &str) -> Result<Decimal, AppError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut digits = String::with_capacity(input.len());
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch == '_' || ch == ' ' {
+            let between_digits = i > 0
+                && chars[i - 1].is_ascii_digit()
+                && chars.get(i + 1).is_some_and(|next| next.is_ascii_digit());
+            if !between_digits {
+                return Err(AppError::AmountParse(input.to_string()));
+            }
+        } else {
+            digits.push(ch);
+        }
+    }
+    digits
+        .parse()
+        .map_err(|_| AppError::AmountParse(input.to_string()))
+}
+
+/// Evaluates an amount that may be an arithmetic expression, e.g. `120 / 3`
+/// for a share of a bill. Numbers are read like in [`parse_amount`], `+`, `-`,
+/// `*`, `/` and parentheses work as usual. With a division, the result is
+/// rounded to cents, e.g. `120 / 7 * 2` is `34.29`.
+pub fn evaluate_amount(input: &str) -> Result<Decimal, AppError> {
+    let error = || AppError::AmountParse(input.to_string());
+    let mut expression = AmountExpression {
+        chars: input.chars().collect(),
+        position: 0,
+        divided: false,
+    };
+    let amount = expression.sum().ok_or_else(error)?;
+    expression.skip_spaces();
+    if expression.position < expression.chars.len() {
+        return Err(error());
+    }
+    if expression.divided {
+        Ok(amount.round_dp(2))
+    } else {
+        Ok(amount)
+    }
+}
+
+/// Recursive descent parser of [`evaluate_amount`], `None` on any error.
+struct AmountExpression {
+    chars: Vec<char>,
+    position: usize,
+    /// Whether the expression has a division, which may leave more digits
+    /// than cents.
+    divided: bool,
+}
+
+impl AmountExpression {
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.position) == Some(&' ') {
+            self.position += 1;
+        }
+    }
+
+    /// Consumes `operator` if it's the next character after spaces.
+    fn eat(&mut self, operator: char) -> bool {
+        self.skip_spaces();
+        let found = self.chars.get(self.position) == Some(&operator);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn sum(&mut self) -> Option<Decimal> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat('+') {
+                value = value.checked_add(self.product()?)?;
+            } else if self.eat('-') {
+                value = value.checked_sub(self.product()?)?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Option<Decimal> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value = value.checked_mul(self.factor()?)?;
+            } else if self.eat('/') {
+                value = value.checked_div(self.factor()?)?;
+                self.divided = true;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Option<Decimal> {
+        if self.eat('-') {
+            return Some(-self.factor()?);
+        }
+        if self.eat('(') {
+            let value = self.sum()?;
+            return self.eat(')').then_some(value);
+        }
+        self.skip_spaces();
+        let start = self.position;
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|ch| ch.is_ascii_digit() || matches!(ch, '.' | '_' | ' '))
+        {
+            self.position += 1;
+        }
+        let number: String = self.chars[start..self.position].iter().collect();
+        parse_amount(number.trim_end()).ok()
+    }
+}
+
+/// Exclusive advisory lock on a data file, released when dropped.
+///
+/// Held around read-modify-write sequences so that concurrent `mfinance`
+/// processes don't interleave their writes to the same file.
+pub struct FileLock {
+    _file: std::fs::File,
+}
+
+/// Path of the lockfile guarding `path`, e.g. `expenses.csv.lock`.
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Locks the file by holding an exclusive lock on its [`lock_path`]. Fails
+/// right away if another process holds it.
+///
+/// The lockfile is left in place, only the lock on it is released. Locking is
+/// skipped on filesystems that don't support it.
+pub fn lock_file(path: &Path) -> Result<FileLock, AppError> {
+    let lock_path = lock_path(path);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|source| AppError::Io {
+            source,
+            context: format!("Failed to open lockfile: {}", lock_path.display()),
+        })?;
+    match fs2::FileExt::try_lock_exclusive(&file) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::Unsupported => {}
+        Err(err) if err.kind() == fs2::lock_contended_error().kind() => {
+            return Err(AppError::Locked(path.display().to_string()));
+        }
+        Err(source) => {
+            return Err(AppError::Io {
+                source,
+                context: format!("Failed to lock file: {}", path.display()),
+            });
+        }
+    }
+    Ok(FileLock { _file: file })
+}
+
+/// Path of the backup copy of `path`, e.g. `expenses.csv.bak`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Copies the file to its [`backup_path`] before it gets rewritten. Without
+/// `overwrite`, an existing backup is kept and the copy goes to the first free
+/// numbered path instead, e.g. `expenses.csv.bak.1`.
+///
+/// Returns the path of the copy, or `None` if the file doesn't exist yet.
+pub fn backup_file(path: &Path, overwrite: bool) -> Result<Option<PathBuf>, AppError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut backup = backup_path(path);
+    if !overwrite {
+        let first = backup.clone();
+        let mut number = 0;
+        while backup.exists() {
+            number += 1;
+            let mut name = first.as_os_str().to_owned();
+            name.push(format!(".{number}"));
+            backup = PathBuf::from(name);
+        }
+    }
+    std::fs::copy(path, &backup).map_err(|source| AppError::Io {
+        source,
+        context: format!("Failed to back up file: {}", path.display()),
+    })?;
+    Ok(Some(backup))
 }
 
 pub fn add_entry(
@@ -62,18 +438,40 @@ pub fn add_entry(
     date: NaiveDate,
     amount: Decimal,
 ) -> Result<NewEntryInfo, AppError> {
-    let entries = entries_from_file(file_path).unwrap_or_default();
-    let total_before: Decimal = entries.iter().map(|entry| entry.amount).sum();
-
     let new_entry = Entry {
-        date: date.to_string(),
+        date,
         amount,
+        full_amount: None,
     };
+    add_entries(file_path, &[new_entry])
+}
+
+/// Appends the entries to the end of the file, in their order, like
+/// [`add_entry`] does for one.
+pub fn add_entries(file_path: &Path, new_entries: &[Entry]) -> Result<NewEntryInfo, AppError> {
+    let _lock = lock_file(file_path)?;
+    append_entries(file_path, new_entries)
+}
+
+/// Same as [`add_entries`], for a caller that already holds the
+/// [`lock_file`] lock, e.g. to back the file up first.
+pub fn append_entries(file_path: &Path, new_entries: &[Entry]) -> Result<NewEntryInfo, AppError> {
+    let entries = entries_from_file(file_path).unwrap_or_default();
+    let amount_format = amount_format_of_file(file_path)?;
+    let total_before: Decimal = entries.iter().map(|entry| entry.amount).sum();
+
+    // A header-only file has no entries but must not get a second header.
+    let is_empty_file = std::fs::metadata(file_path)
+        .map(|metadata| metadata.len() == 0)
+        .unwrap_or(true);
+    let with_full_amount = !is_empty_file && has_full_amount_column(file_path)?;
+    check_amount_format(new_entries, amount_format)?;
 
     // Write to the end of the file.
     let mut writer = WriterBuilder::new()
         .delimiter(DELIMITER)
-        .has_headers(entries.is_empty())
+        .terminator(LineEnding::of_file(file_path).terminator())
+        .has_headers(false)
         .from_writer(
             OpenOptions::new()
                 .create(true)
@@ -85,7 +483,12 @@ pub fn add_entry(
                 })?,
         );
 
-    writer.serialize(new_entry)?;
+    if is_empty_file {
+        writer.write_record(amount_format.headers(false))?;
+    }
+    for new_entry in new_entries {
+        serialize_entry(&mut writer, new_entry, amount_format, with_full_amount)?;
+    }
     writer.flush().map_err(|source| AppError::Io {
         source,
         context: String::from("Failed to flush the writer buffer when saving new entry"),
@@ -127,131 +530,1972 @@ impl<'a> Display for NewEntryInfoDisplay<'a> {
 
         let max_len = [&total_before_line, &diff_line, &total_after_line]
             .iter()
-            .map(|s| s.chars().count())
+            .map(|s| display_width(s))
             .max()
             .unwrap();
 
-        writeln!(f, "{total_before_line:>max_len$}")?;
-        writeln!(f, "{diff_line:>max_len$}")?;
-        writeln!(f, "{total_after_line:>max_len$}")?;
+        writeln!(f, "{}", pad_left(&total_before_line, max_len))?;
+        writeln!(f, "{}", pad_left(&diff_line, max_len))?;
+        writeln!(f, "{}", pad_left(&total_after_line, max_len))?;
         Ok(())
     }
 }
 
-pub fn generate_report(file_path: &Path, date_filter: &str) -> Result<Report, AppError> {
-    let mut entries: Vec<Entry> = entries_from_file(file_path)?
-        .into_iter()
-        .filter(|entry| entry.date.starts_with(date_filter))
-        .collect();
-
-    if entries.is_empty() {
-        return Err(AppError::FilteredNoEntries(date_filter.to_string()));
+/// The `day` of every month from the month of `from` through the month of
+/// `to`. Months shorter than `day` get their last day instead.
+pub fn monthly_dates(from: NaiveDate, to: NaiveDate, day: u32) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let mut month_start = from.with_day(1).expect("every month has a first day");
+    while month_start <= to {
+        let next_month_start = month_start
+            .checked_add_months(chrono::Months::new(1))
+            .expect("date in range");
+        let last_day = next_month_start.pred_opt().expect("date in range").day();
+        dates.push(
+            month_start
+                .with_day(day.clamp(1, last_day))
+                .expect("day within the month"),
+        );
+        month_start = next_month_start;
     }
+    dates
+}
 
-    entries.sort_by(|a, b| a.date.cmp(&b.date));
-    Ok(Report {
-        filter: Some(String::from(date_filter)),
-        entries,
-    })
+/// Simple rate of return of contributions that grew into a final balance.
+pub struct ReturnInfo {
+    pub contributions: Decimal,
+    pub final_balance: Decimal,
 }
 
-pub fn generate_report_for_all(file_path: &Path) -> Result<Report, AppError> {
-    let mut entries = entries_from_file(file_path)?;
-    if entries.is_empty() {
-        return Err(AppError::NoEntries);
+impl ReturnInfo {
+    /// Sums the entries as contributions to the final balance.
+    pub fn new(entries: &[Entry], final_balance: Decimal) -> Self {
+        ReturnInfo {
+            contributions: entries.iter().map(|entry| entry.amount).sum(),
+            final_balance,
+        }
     }
 
-    entries.sort_by(|a, b| a.date.cmp(&b.date));
-    Ok(Report {
-        filter: None,
-        entries,
-    })
-}
+    pub fn gain(&self) -> Decimal {
+        self.final_balance - self.contributions
+    }
 
-pub struct Report {
-    filter: Option<String>,
-    pub entries: Vec<Entry>,
-}
+    /// The gain in percent of the contributions, `None` without contributions.
+    pub fn percent(&self) -> Option<Decimal> {
+        if self.contributions.is_zero() {
+            return None;
+        }
+        Some(self.gain() / self.contributions.abs() * Decimal::ONE_HUNDRED)
+    }
 
-impl Report {
-    pub fn display(&self, options: FormatOptions) -> ReportDisplay<'_> {
-        ReportDisplay {
-            report: self,
+    pub fn display(&self, options: FormatOptions) -> ReturnInfoDisplay<'_> {
+        ReturnInfoDisplay {
+            info: self,
             options,
         }
     }
 }
 
-pub struct ReportDisplay<'a> {
-    report: &'a Report,
+pub struct ReturnInfoDisplay<'a> {
+    info: &'a ReturnInfo,
     options: FormatOptions,
 }
 
-impl<'a> Display for ReportDisplay<'a> {
+impl<'a> Display for ReturnInfoDisplay<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let rows: Vec<(String, String)> = self
-            .report
-            .entries
-            .iter()
-            .map(|entry| {
-                (
-                    format!("{}:", entry.date),
-                    entry.amount.format(&self.options),
-                )
-            })
-            .collect();
-
-        let final_line_prefix: String = if let Some(filter) = self.report.filter.as_ref() {
-            format!("Total amount for filter '{filter}':")
-        } else {
-            "Total amount:".to_string()
+        // A percentage has no currency
+        let percent_options = FormatOptions {
+            currency: number_formatter::CurrencyPosition::None,
+            pad_integer_digits: None,
+            ..self.options.clone()
+        };
+        let percent = match self.info.percent() {
+            Some(percent) => format!("{}%", percent.format(&percent_options)),
+            None => String::from("n/a"),
         };
-        let total: Decimal = self.report.entries.iter().map(|entry| entry.amount).sum();
-        let final_line_suffix: String = total.format(&self.options);
-        let mut max_prefix_len = rows.iter().map(|row| row.0.chars().count()).max().unwrap();
-        let mut max_suffix_len = rows.iter().map(|row| row.1.chars().count()).max().unwrap();
-        max_prefix_len = max_prefix_len.max(final_line_prefix.chars().count());
-        max_suffix_len = max_suffix_len.max(final_line_suffix.chars().count()) + 1;
+        let rows = [
+            (
+                "Contributions:",
+                self.info.contributions.format(&self.options),
+            ),
+            (
+                "Final balance:",
+                self.info.final_balance.format(&self.options),
+            ),
+            ("Gain:", self.info.gain().format(&self.options)),
+            ("Return:", percent),
+        ];
 
+        let max_prefix_len = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+        let max_suffix_len = rows
+            .iter()
+            .map(|row| display_width(&row.1))
+            .max()
+            .unwrap_or(0)
+            + 1;
         for (prefix, suffix) in rows {
-            write!(f, "{prefix:>max_prefix_len$}")?;
-            writeln!(f, "{suffix:>max_suffix_len$}")?;
+            writeln!(
+                f,
+                "{prefix:>max_prefix_len$}{}",
+                pad_left(&suffix, max_suffix_len)
+            )?;
         }
+        Ok(())
+    }
+}
 
-        write!(f, "{final_line_prefix:>max_prefix_len$}")?;
-        writeln!(f, "{final_line_suffix:>max_suffix_len$}")?;
+/// Moves entries dated before `before` from `file_path` into `archive_path`.
+///
+/// Archived entries are merged with whatever the archive already contains and
+/// the archive is kept sorted by date. Both files are written to temporary
+/// files first and then renamed into place, archive first, so a failure can at
+/// worst leave an entry in both files but never lose it.
+pub fn archive_entries(
+    file_path: &Path,
+    archive_path: &Path,
+    before: NaiveDate,
+) -> Result<ArchiveInfo, AppError> {
+    let file_amount_format = amount_format_of_file(file_path)?;
+    let mut moved = Vec::new();
+    let mut remaining = Vec::new();
+    for entry in entries_from_file(file_path)? {
+        if entry.date < before {
+            moved.push(entry);
+        } else {
+            remaining.push(entry);
+        }
+    }
 
-        Ok(())
+    let moved_count = moved.len();
+    let remaining_count = remaining.len();
+    if moved_count == 0 {
+        return Ok(ArchiveInfo {
+            moved: 0,
+            remaining: remaining_count,
+        });
     }
+
+    let (mut archived, archive_amount_format) = if archive_path.exists() {
+        (
+            entries_from_file(archive_path)?,
+            amount_format_of_file(archive_path)?,
+        )
+    } else {
+        (Vec::new(), file_amount_format)
+    };
+    archived.extend(moved);
+    archived.sort_by_key(|entry| entry.date);
+
+    let archive_tmp_path = temp_path_for(archive_path);
+    write_entries(&archive_tmp_path, &archived, archive_amount_format)?;
+    let file_tmp_path = temp_path_for(file_path);
+    write_entries(&file_tmp_path, &remaining, file_amount_format)?;
+    std::fs::rename(&archive_tmp_path, archive_path).map_err(|source| AppError::Io {
+        source,
+        context: format!("Failed to replace archive file: {}", archive_path.display()),
+    })?;
+    std::fs::rename(&file_tmp_path, file_path).map_err(|source| AppError::Io {
+        source,
+        context: format!("Failed to replace file: {}", file_path.display()),
+    })?;
+
+    Ok(ArchiveInfo {
+        moved: moved_count,
+        remaining: remaining_count,
+    })
 }
 
-pub fn entries_from_file(path: &Path) -> Result<Vec<Entry>, AppError> {
-    std::fs::metadata(path).map_err(|e| AppError::Io {
-        source: e,
-        context: format!("Failed to access file: {}", path.display()),
+pub struct ArchiveInfo {
+    pub moved: usize,
+    pub remaining: usize,
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut tmp_file_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_file_name.push(".tmp");
+    path.with_file_name(tmp_file_name)
+}
+
+/// Text of the comment line written by `--stamp`.
+pub fn stamp_comment(today: NaiveDate) -> String {
+    format!(
+        "generated by mfinance v{} on {today}",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// A few example entries with income and expenses over two years, written
+/// by `mfinance init`.
+pub fn sample_entries() -> Vec<Entry> {
+    [
+        ((2024, 9, 11), Decimal::new(150000, 2)),
+        ((2024, 9, 15), Decimal::new(-4250, 2)),
+        ((2024, 10, 1), Decimal::new(-20000, 2)),
+        ((2024, 12, 24), Decimal::new(-8999, 2)),
+        ((2025, 1, 1), Decimal::new(150000, 2)),
+        ((2025, 1, 5), Decimal::new(-7575, 2)),
+    ]
+    .into_iter()
+    .map(|((year, month, day), amount)| Entry {
+        date: NaiveDate::from_ymd_opt(year, month, day).expect("valid sample date"),
+        amount,
+        full_amount: None,
+    })
+    .collect()
+}
+
+/// Overwrites the file with the given entries, storing amounts in `amount_format`.
+pub fn write_entries(
+    path: &Path,
+    entries: &[Entry],
+    amount_format: AmountFormat,
+) -> Result<(), AppError> {
+    write_entries_with_delimiter(path, entries, amount_format, DELIMITER, None)
+}
+
+/// Same as [`write_entries`], but separates fields with `delimiter` and, if
+/// `stamp` is given, starts the file with it as a `#` comment line.
+pub fn write_entries_with_delimiter(
+    path: &Path,
+    entries: &[Entry],
+    amount_format: AmountFormat,
+    delimiter: u8,
+    stamp: Option<&str>,
+) -> Result<(), AppError> {
+    check_amount_format(entries, amount_format)?;
+    let line_ending = LineEnding::of_file(path);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .map_err(|source| AppError::Io {
+            source,
+            context: format!("Failed to open file to write entries: {}", path.display()),
+        })?;
+    if let Some(stamp) = stamp {
+        let newline = line_ending.as_str();
+        write!(file, "{} {stamp}{newline}", COMMENT as char).map_err(|source| AppError::Io {
+            source,
+            context: format!("Failed to write the stamp to {}", path.display()),
+        })?;
+    }
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .terminator(line_ending.terminator())
+        .has_headers(false)
+        .from_writer(file);
+
+    // The full amount column is only written when some entry has one.
+    let with_full_amount = entries.iter().any(|entry| entry.full_amount.is_some());
+    writer.write_record(amount_format.headers(with_full_amount))?;
+    for entry in entries {
+        serialize_entry(&mut writer, entry, amount_format, with_full_amount)?;
+    }
+    writer.flush().map_err(|source| AppError::Io {
+        source,
+        context: format!("Failed to flush the writer buffer for {}", path.display()),
     })?;
+    Ok(())
+}
+
+/// Fails if an entry can't be stored in `amount_format`, checked before
+/// anything is written so that a failure leaves the file as it was.
+fn check_amount_format(entries: &[Entry], amount_format: AmountFormat) -> Result<(), AppError> {
+    if amount_format == AmountFormat::Cents {
+        for entry in entries {
+            CentsEntry::try_from(entry)?;
+        }
+    }
+    Ok(())
+}
+
+fn serialize_entry<W: std::io::Write>(
+    writer: &mut csv::Writer<W>,
+    entry: &Entry,
+    amount_format: AmountFormat,
+    with_full_amount: bool,
+) -> Result<(), AppError> {
+    match (amount_format, with_full_amount) {
+        (AmountFormat::Decimal, true) => writer.serialize(entry)?,
+        (AmountFormat::Decimal, false) => writer.serialize((entry.date, entry.amount))?,
+        (AmountFormat::Cents, true) => writer.serialize(CentsEntry::try_from(entry)?)?,
+        (AmountFormat::Cents, false) => {
+            let entry = CentsEntry::try_from(entry)?;
+            writer.serialize((entry.date, entry.amount_cents))?
+        }
+    }
+    Ok(())
+}
 
+/// Rewrites the file with its amounts stored in `amount_format`, returning
+/// the number of entries. Fails instead of rounding amounts that don't fit
+/// into whole cents.
+pub fn migrate_entries(path: &Path, amount_format: AmountFormat) -> Result<usize, AppError> {
+    let entries = entries_from_file(path)?;
+    write_entries(path, &entries, amount_format)?;
+    Ok(entries.len())
+}
+
+/// Hashes the entries with BLAKE3 into a hex checksum that doesn't depend on
+/// how the file stores them: `700`, `700.00` and `70000` cents are the same
+/// amount. Unless `ordered`, the order of the entries doesn't matter either.
+pub fn checksum(entries: &[Entry], ordered: bool) -> String {
+    let mut lines: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            let full_amount = entry
+                .full_amount
+                .map(|amount| amount.normalize().to_string())
+                .unwrap_or_default();
+            format!(
+                "{};{};{full_amount}\n",
+                entry.date,
+                entry.amount.normalize()
+            )
+        })
+        .collect();
+    if !ordered {
+        lines.sort_unstable();
+    }
+    let mut hasher = blake3::Hasher::new();
+    for line in &lines {
+        hasher.update(line.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Whether the file has a `full_amount` (or `full_amount_cents`) column.
+fn has_full_amount_column(path: &Path) -> Result<bool, AppError> {
     let mut reader = ReaderBuilder::new()
         .delimiter(DELIMITER)
-        .from_path(path)
-        .map_err(|source| AppError::Csv { source })?;
-    let entries = reader
-        .deserialize::<Entry>()
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(entries)
+        .comment(Some(COMMENT))
+        .from_path(path)?;
+    Ok(reader
+        .headers()?
+        .iter()
+        .any(|header| header.starts_with("full_amount")))
 }
 
-pub fn get_csv_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
-    let mut files = std::fs::read_dir(dir)?
-        .filter_map(|entry| {
-            let path = entry.ok()?.path();
-            if path.extension()?.to_str()? == "csv" {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    files.sort();
-    Ok(files)
+/// Detects how amounts are stored in the file, defaulting to decimals for
+/// missing or empty files.
+pub fn amount_format_of_file(path: &Path) -> Result<AmountFormat, AppError> {
+    amount_format_of_file_with_delimiter(path, DELIMITER)
+}
+
+/// Same as [`amount_format_of_file`], but reads a file separated by `delimiter`.
+pub fn amount_format_of_file_with_delimiter(
+    path: &Path,
+    delimiter: u8,
+) -> Result<AmountFormat, AppError> {
+    if !path.exists() {
+        return Ok(AmountFormat::default());
+    }
+
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(COMMENT))
+        .from_path(path)?;
+    Ok(AmountFormat::from_headers(reader.headers()?))
+}
+
+/// Amount column a report sums.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum AmountColumn {
+    /// The own share, `amount`
+    #[default]
+    Amount,
+    /// The total of shared expenses, `full_amount`, or `amount` where it's empty
+    FullAmount,
+}
+
+/// Order to show the entries of a report in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    Date,
+    Amount,
+}
+
+/// Sign of the amounts a report keeps, zero amounts have neither.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sign {
+    Negative,
+    Positive,
+}
+
+/// Days of the week a report keeps.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DayKind {
+    /// Monday to Friday.
+    Weekday,
+    /// Saturday and Sunday.
+    Weekend,
+}
+
+/// Calendar period used to group entries into subtotals.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum Period {
+    Year,
+    Quarter,
+    Month,
+    Day,
+}
+
+impl Period {
+    /// Label of the period containing `date`, e.g. `2024`, `2024-Q4`, `2024-10`
+    /// or `2024-10-02`.
+    pub fn label(&self, date: NaiveDate) -> String {
+        match self {
+            Period::Year => date.year().to_string(),
+            Period::Quarter => format!("{}-Q{}", date.year(), (date.month() - 1) / 3 + 1),
+            Period::Month => date.format("%Y-%m").to_string(),
+            Period::Day => date.to_string(),
+        }
+    }
+
+    /// First day of the period following the one containing `date`.
+    fn next_start(&self, date: NaiveDate) -> NaiveDate {
+        let months = match self {
+            Period::Day => return date.succ_opt().expect("date before the end of time"),
+            Period::Month => 1,
+            Period::Quarter => 3 - (date.month0() % 3),
+            Period::Year => 12 - date.month0(),
+        };
+        date.with_day(1)
+            .and_then(|start| start.checked_add_months(Months::new(months)))
+            .expect("date before the end of time")
+    }
+}
+
+/// Subtotal of all entries within one period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodTotal {
+    pub period: String,
+    pub total: Decimal,
+    pub count: usize,
+}
+
+/// Groups entries by period, ordered chronologically.
+pub fn group_by_period(entries: &[Entry], period: Period) -> Vec<PeriodTotal> {
+    let mut groups: BTreeMap<String, PeriodTotal> = BTreeMap::new();
+    for entry in entries {
+        let label = period.label(entry.date);
+        let group = groups.entry(label.clone()).or_insert(PeriodTotal {
+            period: label,
+            total: Decimal::ZERO,
+            count: 0,
+        });
+        group.total += entry.amount;
+        group.count += 1;
+    }
+    groups.into_values().collect()
+}
+
+/// Change of a period's subtotal versus the period before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodDelta {
+    pub change: Decimal,
+    /// The change in percent of the previous subtotal, `None` if that was zero.
+    pub percent: Option<Decimal>,
+}
+
+/// The [`PeriodDelta`] of each group versus the group before it, `None` for
+/// the first group.
+pub fn period_deltas(groups: &[PeriodTotal]) -> Vec<Option<PeriodDelta>> {
+    let mut previous: Option<Decimal> = None;
+    groups
+        .iter()
+        .map(|group| {
+            let delta = previous.map(|previous| {
+                let change = group.total - previous;
+                PeriodDelta {
+                    change,
+                    percent: (!previous.is_zero())
+                        .then(|| change / previous.abs() * Decimal::ONE_HUNDRED),
+                }
+            });
+            previous = Some(group.total);
+            delta
+        })
+        .collect()
+}
+
+/// Pairs each entry with the balance after it, the sum of its amount and the
+/// amounts of all entries before it. Entries are taken in the given order.
+pub fn running_balances<'a>(
+    entries: impl IntoIterator<Item = &'a Entry>,
+) -> impl Iterator<Item = (&'a Entry, Decimal)> {
+    entries.into_iter().scan(Decimal::ZERO, |balance, entry| {
+        *balance += entry.amount;
+        Some((entry, *balance))
+    })
+}
+
+/// Rounds the amounts to cents so that they add up to their rounded sum, by
+/// rounding down and giving the missing cents to the amounts that lost the
+/// most (the largest remainder method).
+pub fn reconcile_rounding(amounts: &[Decimal]) -> Vec<Decimal> {
+    let cent = Decimal::new(1, 2);
+    let mut rounded: Vec<Decimal> = amounts
+        .iter()
+        .map(|amount| amount.round_dp_with_strategy(2, RoundingStrategy::ToNegativeInfinity))
+        .collect();
+    let total = amounts.iter().sum::<Decimal>().round_dp(2);
+    let missing_cents = ((total - rounded.iter().sum::<Decimal>()) / cent)
+        .to_usize()
+        .unwrap_or_default();
+    let mut by_remainder: Vec<usize> = (0..amounts.len()).collect();
+    // Stable sort gives equal remainders to the earlier amounts first
+    by_remainder.sort_by_key(|&i| std::cmp::Reverse(amounts[i] - rounded[i]));
+    for i in by_remainder.into_iter().take(missing_cents) {
+        rounded[i] += cent;
+    }
+    rounded
+}
+
+/// Inserts empty subtotals for the periods from `first` to `last` missing in
+/// `groups`, so that the series has no holes.
+fn fill_period_gaps(
+    groups: Vec<PeriodTotal>,
+    period: Period,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Vec<PeriodTotal> {
+    let mut groups = groups.into_iter().peekable();
+    let mut filled = Vec::new();
+    let mut date = first;
+    while date <= last {
+        let label = period.label(date);
+        filled.push(
+            groups
+                .next_if(|group| group.period == label)
+                .unwrap_or(PeriodTotal {
+                    period: label,
+                    total: Decimal::ZERO,
+                    count: 0,
+                }),
+        );
+        date = period.next_start(date);
+    }
+    filled
+}
+
+pub fn generate_report(file_path: &Path, date_filter: &str) -> Result<Report, AppError> {
+    generate_report_with_options(file_path, date_filter, ReadOptions::default())
+}
+
+/// Same as [`generate_report`], but reads the file with `options`.
+pub fn generate_report_with_options(
+    file_path: &Path,
+    date_filter: &str,
+    options: ReadOptions,
+) -> Result<Report, AppError> {
+    let mut entries: Vec<Entry> = entries_from_file_with_options(file_path, DELIMITER, options)?
+        .into_iter()
+        .filter(|entry| entry.date.to_string().starts_with(date_filter))
+        .collect();
+
+    if entries.is_empty() {
+        return Err(AppError::FilteredNoEntries(date_filter.to_string()));
+    }
+
+    entries.sort_by_key(|entry| entry.date);
+    Ok(Report {
+        filter: Some(String::from(date_filter)),
+        exclude_filter: None,
+        fill_gaps: false,
+        entries,
+    })
+}
+
+pub fn generate_report_for_all(file_path: &Path) -> Result<Report, AppError> {
+    generate_report_for_all_with_options(file_path, ReadOptions::default())
+}
+
+/// Same as [`generate_report_for_all`], but reads the file with `options`.
+pub fn generate_report_for_all_with_options(
+    file_path: &Path,
+    options: ReadOptions,
+) -> Result<Report, AppError> {
+    let mut entries = entries_from_file_with_options(file_path, DELIMITER, options)?;
+    if entries.is_empty() {
+        return Err(AppError::NoEntries);
+    }
+
+    entries.sort_by_key(|entry| entry.date);
+    Ok(Report {
+        filter: None,
+        exclude_filter: None,
+        fill_gaps: false,
+        entries,
+    })
+}
+
+pub struct Report {
+    filter: Option<String>,
+    exclude_filter: Option<String>,
+    /// Whether subtotals include empty periods between the first and the last.
+    fill_gaps: bool,
+    pub entries: Vec<Entry>,
+}
+
+impl Report {
+    /// A report without entries, used when an empty result is not an error.
+    pub fn empty(filter: Option<String>) -> Self {
+        Report {
+            filter,
+            exclude_filter: None,
+            fill_gaps: false,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Makes the per-period subtotals include empty periods between the first
+    /// and the last one.
+    pub fn fill_gaps(mut self, fill_gaps: bool) -> Self {
+        self.fill_gaps = fill_gaps;
+        self
+    }
+
+    /// Subtotals of the entries per period, ordered chronologically.
+    pub fn grouped(&self, period: Period) -> Vec<PeriodTotal> {
+        let groups = group_by_period(&self.entries, period);
+        match (self.fill_gaps, self.entries.first(), self.entries.last()) {
+            (true, Some(first), Some(last)) => {
+                fill_period_gaps(groups, period, first.date, last.date)
+            }
+            _ => groups,
+        }
+    }
+
+    /// Drops entries whose date starts with `date_filter`, the inverse of the
+    /// filter of [`generate_report`].
+    pub fn excluding(mut self, date_filter: &str) -> Result<Report, AppError> {
+        self.entries
+            .retain(|entry| !entry.date.to_string().starts_with(date_filter));
+        self.exclude_filter = Some(String::from(date_filter));
+        if self.entries.is_empty() {
+            let filters = match &self.filter {
+                Some(filter) => format!("{filter} excluding {date_filter}"),
+                None => format!("excluding {date_filter}"),
+            };
+            return Err(AppError::FilteredNoEntries(filters));
+        }
+        Ok(self)
+    }
+
+    /// Keeps only entries dated after `date`, e.g. after the last marked report.
+    pub fn newer_than(mut self, date: NaiveDate) -> Result<Report, AppError> {
+        self.entries.retain(|entry| entry.date > date);
+        if self.entries.is_empty() {
+            return Err(AppError::NoNewEntries(date));
+        }
+        Ok(self)
+    }
+
+    /// Keeps only entries dated on or before `date`, so that the total is the
+    /// balance as of that date.
+    pub fn as_of(mut self, date: NaiveDate) -> Result<Report, AppError> {
+        self.entries.retain(|entry| entry.date <= date);
+        if self.entries.is_empty() {
+            return Err(AppError::NoEntries);
+        }
+        Ok(self)
+    }
+
+    /// Keeps only entries dated strictly after `after` and strictly before
+    /// `before`, where given.
+    pub fn between(
+        mut self,
+        after: Option<NaiveDate>,
+        before: Option<NaiveDate>,
+    ) -> Result<Report, AppError> {
+        self.entries.retain(|entry| {
+            after.is_none_or(|after| entry.date > after)
+                && before.is_none_or(|before| entry.date < before)
+        });
+        if self.entries.is_empty() {
+            return Err(AppError::NoEntries);
+        }
+        Ok(self)
+    }
+
+    /// Keeps only entries with amounts of the given sign, e.g. only expenses.
+    pub fn with_sign(mut self, sign: Sign) -> Result<Report, AppError> {
+        self.entries.retain(|entry| match sign {
+            Sign::Negative => entry.amount < Decimal::ZERO,
+            Sign::Positive => entry.amount > Decimal::ZERO,
+        });
+        if self.entries.is_empty() {
+            return Err(AppError::NoEntries);
+        }
+        Ok(self)
+    }
+
+    /// Keeps only entries dated on the given kind of day, e.g. only weekdays
+    /// for business expenses.
+    pub fn on_days(mut self, kind: DayKind) -> Result<Report, AppError> {
+        self.entries.retain(|entry| {
+            let is_weekend = matches!(
+                entry.date.weekday(),
+                chrono::Weekday::Sat | chrono::Weekday::Sun
+            );
+            match kind {
+                DayKind::Weekday => !is_weekend,
+                DayKind::Weekend => is_weekend,
+            }
+        });
+        if self.entries.is_empty() {
+            return Err(AppError::NoEntries);
+        }
+        Ok(self)
+    }
+
+    /// Reports entries with the amounts of `column`.
+    pub fn with_column(mut self, column: AmountColumn) -> Self {
+        if column == AmountColumn::FullAmount {
+            for entry in &mut self.entries {
+                entry.amount = entry.full_amount.unwrap_or(entry.amount);
+            }
+        }
+        self
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.entries.iter().map(|entry| entry.amount).sum()
+    }
+
+    /// Serializes the report as JSON, with amounts both as exact decimal
+    /// strings and formatted for display.
+    pub fn to_json(&self, options: &FormatOptions) -> serde_json::Value {
+        let entries: Vec<serde_json::Value> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "date": entry.date,
+                    "amount": entry.amount.to_string(),
+                    "formatted_amount": entry.amount.format(options),
+                })
+            })
+            .collect();
+        let total = self.total();
+        serde_json::json!({
+            "filter": self.filter,
+            "entries": entries,
+            "total": total.to_string(),
+            "formatted_total": total.format(options),
+        })
+    }
+
+    /// Serializes per-period subtotals as JSON, like [`Report::to_json`] does
+    /// with entries. With `deltas`, each subtotal also has its change versus
+    /// the one before it, see [`period_deltas`].
+    pub fn to_json_grouped(
+        &self,
+        period: Period,
+        deltas: bool,
+        options: &FormatOptions,
+    ) -> serde_json::Value {
+        let groups = self.grouped(period);
+        let period_deltas = period_deltas(&groups);
+        let groups: Vec<serde_json::Value> = groups
+            .into_iter()
+            .zip(period_deltas)
+            .map(|(group, delta)| {
+                let mut json = serde_json::json!({
+                    "period": group.period,
+                    "count": group.count,
+                    "total": group.total.to_string(),
+                    "formatted_total": group.total.format(options),
+                });
+                if deltas {
+                    json["change"] =
+                        serde_json::json!(delta.as_ref().map(|d| d.change.to_string()));
+                    json["change_percent"] = serde_json::json!(
+                        delta
+                            .and_then(|d| d.percent)
+                            .map(|percent| percent.round_dp(2).to_string())
+                    );
+                }
+                json
+            })
+            .collect();
+        let total = self.total();
+        serde_json::json!({
+            "filter": self.filter,
+            "groups": groups,
+            "total": total.to_string(),
+            "formatted_total": total.format(options),
+        })
+    }
+
+    /// Renders the entries, or per-period subtotals, as a Markdown table with
+    /// a right-aligned amount column and a bold total row.
+    pub fn to_markdown(&self, group_by: Option<Period>, options: &FormatOptions) -> String {
+        let cell = |text: String| text.replace('|', "\\|");
+        let mut table = String::new();
+        match group_by {
+            Some(period) => {
+                table.push_str("| Period | Entries | Amount |\n| --- | ---: | ---: |\n");
+                for group in self.grouped(period) {
+                    table.push_str(&format!(
+                        "| {} | {} | {} |\n",
+                        group.period,
+                        group.count,
+                        cell(group.total.format(options))
+                    ));
+                }
+                table.push_str(&format!(
+                    "| **Total** | **{}** | **{}** |\n",
+                    self.entries.len(),
+                    cell(self.total().format(options))
+                ));
+            }
+            None => {
+                table.push_str("| Date | Amount |\n| --- | ---: |\n");
+                for entry in &self.entries {
+                    table.push_str(&format!(
+                        "| {} | {} |\n",
+                        entry.date,
+                        cell(entry.amount.format(options))
+                    ));
+                }
+                table.push_str(&format!(
+                    "| **Total** | **{}** |\n",
+                    cell(self.total().format(options))
+                ));
+            }
+        }
+        table
+    }
+
+    /// Serializes the report entries as CSV in the same layout as the data files.
+    pub fn to_csv(&self, delimiter: u8) -> String {
+        let mut writer = WriterBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .from_writer(Vec::new());
+        writer
+            .write_record(AmountFormat::Decimal.headers(false))
+            .expect("writing CSV into memory never fails");
+        for entry in &self.entries {
+            serialize_entry(&mut writer, entry, AmountFormat::Decimal, false)
+                .expect("writing CSV into memory never fails");
+        }
+        let bytes = writer
+            .into_inner()
+            .expect("writing CSV into memory never fails");
+        String::from_utf8(bytes).expect("CSV is written from UTF-8 strings")
+    }
+
+    /// Writes each entry as a ledger journal transaction posting to
+    /// `Assets:<account>`, balanced by an implicit `Equity:Unassigned` posting.
+    pub fn to_ledger(&self, account: &str) -> String {
+        let mut journal = String::new();
+        for entry in &self.entries {
+            journal.push_str(&format!(
+                "{} {account}\n    Assets:{account}  {:.2}\n    Equity:Unassigned\n\n",
+                entry.date, entry.amount
+            ));
+        }
+        journal
+    }
+
+    /// Serializes per-period subtotals as a `period;total` CSV, optionally
+    /// followed by a `TOTAL` row with the grand total.
+    pub fn to_csv_summary(&self, period: Period, with_total: bool) -> Result<String, AppError> {
+        let mut writer = WriterBuilder::new()
+            .delimiter(DELIMITER)
+            .from_writer(Vec::new());
+        writer.write_record(["period", "total"])?;
+        for period_total in self.grouped(period) {
+            writer.write_record([period_total.period, period_total.total.to_string()])?;
+        }
+        if with_total {
+            writer.write_record([String::from("TOTAL"), self.total().to_string()])?;
+        }
+        let bytes = writer.into_inner().map_err(|err| AppError::Io {
+            source: err.into_error(),
+            context: String::from("Failed to write the CSV summary"),
+        })?;
+        Ok(String::from_utf8(bytes).expect("CSV is written from UTF-8 strings"))
+    }
+
+    pub fn display(&self, options: FormatOptions) -> ReportDisplay<'_> {
+        ReportDisplay {
+            report: self,
+            options,
+            with_total: true,
+            top: None,
+            with_indices: false,
+            group_by: None,
+            title: None,
+            opening_balance: None,
+            running_min_max: false,
+            reconcile_rounding: false,
+            sort_by: None,
+            descending: false,
+            percent: false,
+            deltas: false,
+        }
+    }
+}
+
+/// Path of the sidecar file that remembers the last reported date of a file.
+pub fn report_marker_path(file_path: &Path) -> PathBuf {
+    let mut marker_file_name = file_path.file_name().unwrap_or_default().to_os_string();
+    marker_file_name.push(".last-report");
+    file_path.with_file_name(marker_file_name)
+}
+
+/// Returns the date stored by the last marked report, if any.
+pub fn read_report_marker(file_path: &Path) -> Result<Option<NaiveDate>, AppError> {
+    let marker_path = report_marker_path(file_path);
+    if !marker_path.exists() {
+        return Ok(None);
+    }
+
+    let marker = std::fs::read_to_string(&marker_path).map_err(|source| AppError::Io {
+        source,
+        context: format!("Failed to read report marker: {}", marker_path.display()),
+    })?;
+    let marker = marker.trim();
+    let date = marker.parse().map_err(|source| AppError::DateParse {
+        source,
+        input: marker.to_string(),
+    })?;
+    Ok(Some(date))
+}
+
+/// Remembers the latest date of the report so the next `--new-only` report starts after it.
+pub fn write_report_marker(file_path: &Path, report: &Report) -> Result<(), AppError> {
+    let Some(last_date) = report.entries.iter().map(|entry| entry.date).max() else {
+        return Ok(());
+    };
+
+    let marker_path = report_marker_path(file_path);
+    std::fs::write(&marker_path, format!("{last_date}\n")).map_err(|source| AppError::Io {
+        source,
+        context: format!("Failed to write report marker: {}", marker_path.display()),
+    })
+}
+
+/// Number of terminal columns `text` takes, with wide characters like CJK
+/// taking two and combining marks none.
+pub fn display_width(text: &str) -> usize {
+    unicode_width::UnicodeWidthStr::width(text)
+}
+
+/// Right-aligns `text` to `width` terminal columns, see [`display_width`].
+pub fn pad_left(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{}{text}", " ".repeat(padding))
+}
+
+pub struct ReportDisplay<'a> {
+    report: &'a Report,
+    options: FormatOptions,
+    with_total: bool,
+    top: Option<usize>,
+    with_indices: bool,
+    group_by: Option<Period>,
+    title: Option<String>,
+    opening_balance: Option<Decimal>,
+    running_min_max: bool,
+    reconcile_rounding: bool,
+    sort_by: Option<SortKey>,
+    descending: bool,
+    percent: bool,
+    deltas: bool,
+}
+
+impl ReportDisplay<'_> {
+    /// Whether to end the report with the total line, on by default.
+    pub fn with_total(mut self, with_total: bool) -> Self {
+        self.with_total = with_total;
+        self
+    }
+
+    /// Shows only the `top` entries with the largest absolute amounts, largest
+    /// first, while the total still covers every entry of the report.
+    pub fn top(mut self, top: Option<usize>) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Prefixes each entry with its 1-based position in the report.
+    pub fn with_indices(mut self, with_indices: bool) -> Self {
+        self.with_indices = with_indices;
+        self
+    }
+
+    /// Shows a subtotal per period instead of the entries.
+    pub fn group_by(mut self, group_by: Option<Period>) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    /// Starts the report with a title line, centered over the columns.
+    pub fn title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Follows each entry with the running balance after it, starting from
+    /// the opening balance, which the total then includes as well.
+    pub fn opening_balance(mut self, opening_balance: Option<Decimal>) -> Self {
+        self.opening_balance = opening_balance;
+        self
+    }
+
+    /// Follows each entry with the running balance and the lowest and the
+    /// highest balance reached so far.
+    pub fn running_min_max(mut self, running_min_max: bool) -> Self {
+        self.running_min_max = running_min_max;
+        self
+    }
+
+    /// Rounds the amounts of the rows so that they add up to the shown total,
+    /// see [`reconcile_rounding`].
+    pub fn reconcile_rounding(mut self, reconcile_rounding: bool) -> Self {
+        self.reconcile_rounding = reconcile_rounding;
+        self
+    }
+
+    /// Shows the entries ordered by `sort_by` instead of by date, keeping the
+    /// order of equal ones.
+    pub fn sort_by(mut self, sort_by: Option<SortKey>) -> Self {
+        self.sort_by = sort_by;
+        self
+    }
+
+    /// Reverses the order of [`ReportDisplay::sort_by`], e.g. largest first.
+    pub fn descending(mut self, descending: bool) -> Self {
+        self.descending = descending;
+        self
+    }
+
+    /// Shows the amounts as percentages, e.g. of a budget allocation, and
+    /// ends the report with whether they sum to 100.
+    pub fn percent(mut self, percent: bool) -> Self {
+        self.percent = percent;
+        self
+    }
+
+    /// The total the report ends with, including the opening balance.
+    pub fn total(&self) -> Decimal {
+        self.report.total() + self.opening_balance.unwrap_or_default()
+    }
+
+    /// Follows each subtotal of [`ReportDisplay::group_by`] with its change
+    /// versus the previous period, absolute and in percent.
+    pub fn deltas(mut self, deltas: bool) -> Self {
+        self.deltas = deltas;
+        self
+    }
+}
+
+impl<'a> Display for ReportDisplay<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Percentages take a `%` suffix in place of the currency
+        let options = if self.percent {
+            FormatOptions {
+                currency: number_formatter::CurrencyPosition::Suffix("%".to_string()),
+                pad_integer_digits: None,
+                ..self.options.clone()
+            }
+        } else {
+            self.options.clone()
+        };
+        // Indices are 1-based positions in the report, before any reordering
+        let mut entries: Vec<(usize, &Entry)> = self
+            .report
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (i + 1, entry))
+            .collect();
+        if let Some(top) = self.top {
+            // Stable sort keeps the date order of equal amounts
+            entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.amount.abs()));
+            entries.truncate(top);
+        }
+        if let Some(sort_by) = self.sort_by {
+            entries.sort_by(|(_, a), (_, b)| {
+                let ordering = match sort_by {
+                    SortKey::Date => a.date.cmp(&b.date),
+                    SortKey::Amount => a.amount.cmp(&b.amount),
+                };
+                if self.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        // Rows are a label, an amount and a note after the amount
+        let reconciled = |amounts: Vec<Decimal>| {
+            if self.reconcile_rounding {
+                reconcile_rounding(&amounts)
+            } else {
+                amounts
+            }
+        };
+        let rows: Vec<(String, String, String)> = if let Some(period) = self.group_by {
+            let groups = self.report.grouped(period);
+            let totals = reconciled(groups.iter().map(|group| group.total).collect());
+            let counts: Vec<String> = groups
+                .iter()
+                .map(|group| {
+                    let noun = if group.count == 1 { "entry" } else { "entries" };
+                    format!("({} {noun})", group.count)
+                })
+                .collect();
+            // The change and the percent change, a dash for the first period
+            let percent_options = FormatOptions {
+                currency: number_formatter::CurrencyPosition::None,
+                pad_integer_digits: None,
+                ..options.clone()
+            };
+            let signed = |value: Decimal, text: String| {
+                if value > Decimal::ZERO {
+                    format!("+{text}")
+                } else {
+                    text
+                }
+            };
+            let deltas: Vec<[String; 2]> = period_deltas(&groups)
+                .into_iter()
+                .map(|delta| match delta {
+                    Some(delta) => [
+                        signed(delta.change, delta.change.format(&options)),
+                        delta.percent.map_or_else(
+                            || String::from("n/a"),
+                            |percent| {
+                                signed(percent, format!("{}%", percent.format(&percent_options)))
+                            },
+                        ),
+                    ],
+                    None => [String::from("-"), String::from("-")],
+                })
+                .collect();
+            let count_width = counts.iter().map(|count| count.len()).max().unwrap_or(0);
+            let [change_width, percent_width] = std::array::from_fn(|column| {
+                deltas
+                    .iter()
+                    .map(|row| display_width(&row[column]))
+                    .max()
+                    .unwrap_or(0)
+            });
+            groups
+                .into_iter()
+                .zip(totals)
+                .zip(counts.into_iter().zip(deltas))
+                .map(|((group, total), (count, [change, percent]))| {
+                    let note = if self.deltas {
+                        format!(
+                            "  {count:<count_width$}  {}  {}",
+                            pad_left(&change, change_width),
+                            pad_left(&percent, percent_width)
+                        )
+                    } else {
+                        format!("  {count}")
+                    };
+                    (format!("{}:", group.period), total.format(&options), note)
+                })
+                .collect()
+        } else {
+            // Reconciled over all entries, as the total covers even those
+            // left out by `top`
+            let amounts = reconciled(
+                self.report
+                    .entries
+                    .iter()
+                    .map(|entry| entry.amount)
+                    .collect(),
+            );
+            // The running balance, its minimum and its maximum after each entry
+            let opening_balance = self.opening_balance.unwrap_or_default();
+            let mut min_max: Option<(Decimal, Decimal)> = None;
+            let balances: Vec<[String; 3]> = running_balances(entries.iter().map(|(_, e)| *e))
+                .map(|(_, balance)| {
+                    let balance = opening_balance + balance;
+                    let (min, max) = min_max.map_or((balance, balance), |(min, max)| {
+                        (min.min(balance), max.max(balance))
+                    });
+                    min_max = Some((min, max));
+                    [balance, min, max].map(|value| value.format(&options))
+                })
+                .collect();
+            let widths: [usize; 3] = std::array::from_fn(|column| {
+                balances
+                    .iter()
+                    .map(|row| display_width(&row[column]))
+                    .max()
+                    .unwrap_or(0)
+            });
+            let with_balance = self.opening_balance.is_some() || self.running_min_max;
+            entries
+                .into_iter()
+                .zip(balances)
+                .map(|((index, entry), [balance, min, max])| {
+                    let prefix = if self.with_indices {
+                        format!("{index}. {}:", entry.date)
+                    } else {
+                        format!("{}:", entry.date)
+                    };
+                    let [balance_width, min_width, max_width] = widths;
+                    let mut note = String::new();
+                    if with_balance {
+                        note = format!("  {}", pad_left(&balance, balance_width));
+                    }
+                    if self.running_min_max {
+                        note += &format!(
+                            "  min {}  max {}",
+                            pad_left(&min, min_width),
+                            pad_left(&max, max_width)
+                        );
+                    }
+                    (prefix, amounts[index - 1].format(&options), note)
+                })
+                .collect()
+        };
+
+        let final_line_prefix: String = match (&self.report.filter, &self.report.exclude_filter) {
+            (Some(filter), Some(exclude)) => {
+                format!("Total amount for filter '{filter}' excluding '{exclude}':")
+            }
+            (Some(filter), None) => format!("Total amount for filter '{filter}':"),
+            (None, Some(exclude)) => format!("Total amount excluding '{exclude}':"),
+            (None, None) => "Total amount:".to_string(),
+        };
+        let total = self.total();
+        let final_line_suffix: String = total.format(&options);
+        let percent_line = self.percent.then(|| {
+            // Allow for a hundredth of a percent lost to rounding
+            let complete = (total - Decimal::ONE_HUNDRED).abs() <= Decimal::new(1, 2);
+            ("Sums to 100%?", if complete { "yes" } else { "no" })
+        });
+        let mut max_prefix_len = rows
+            .iter()
+            .map(|row| display_width(&row.0))
+            .max()
+            .unwrap_or(0);
+        let mut max_suffix_len = rows
+            .iter()
+            .map(|row| display_width(&row.1))
+            .max()
+            .unwrap_or(0);
+        if self.with_total {
+            max_prefix_len = max_prefix_len.max(display_width(&final_line_prefix));
+            max_suffix_len = max_suffix_len.max(display_width(&final_line_suffix));
+        }
+        if let Some((prefix, suffix)) = percent_line {
+            max_prefix_len = max_prefix_len.max(display_width(prefix));
+            max_suffix_len = max_suffix_len.max(display_width(suffix));
+        }
+        max_suffix_len += 1;
+
+        // The title doesn't widen the columns, a longer one just sticks out
+        if let Some(title) = &self.title {
+            let width = max_prefix_len + max_suffix_len;
+            // Like `{:^}`, an odd leftover column goes to the right
+            let indent = width.saturating_sub(display_width(title)) / 2;
+            writeln!(f, "{}{title}", " ".repeat(indent))?;
+        }
+
+        // Amounts always have the same number of fraction digits and the same currency
+        // suffix, so right-aligning them also lines up their decimal separators.
+        for (prefix, suffix, note) in rows {
+            write!(f, "{}", pad_left(&prefix, max_prefix_len))?;
+            writeln!(f, "{}{note}", pad_left(&suffix, max_suffix_len))?;
+        }
+
+        if self.with_total {
+            write!(f, "{}", pad_left(&final_line_prefix, max_prefix_len))?;
+            writeln!(f, "{}", pad_left(&final_line_suffix, max_suffix_len))?;
+        }
+        if let Some((prefix, suffix)) = percent_line {
+            write!(f, "{}", pad_left(prefix, max_prefix_len))?;
+            writeln!(f, "{}", pad_left(suffix, max_suffix_len))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn entries_from_file(path: &Path) -> Result<Vec<Entry>, AppError> {
+    entries_from_file_with_delimiter(path, DELIMITER)
+}
+
+/// Renders the line of `path` a CSV error points at, with the offending field
+/// underlined like in a compiler diagnostic. The whole line is underlined when
+/// the error isn't about a single field.
+pub fn error_snippet(path: &Path, error: &AppError, delimiter: u8) -> Option<String> {
+    let AppError::Csv { source } = error else {
+        return None;
+    };
+    let line_number = source.position()?.line();
+    let content = std::fs::read_to_string(path).ok()?;
+    let line = content
+        .lines()
+        .nth(usize::try_from(line_number).ok()?.checked_sub(1)?)?;
+
+    let fields: Vec<&str> = line.split(delimiter as char).collect();
+    let field = match source.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => match err.field() {
+            Some(field) => usize::try_from(field).ok(),
+            // Errors of the column types don't say which field they are about
+            None => {
+                let headers = content
+                    .trim_start_matches('\u{feff}')
+                    .lines()
+                    .find(|line| !line.starts_with(COMMENT as char))?;
+                headers
+                    .split(delimiter as char)
+                    .zip(&fields)
+                    .position(|(header, value)| !is_valid_field(header, value))
+            }
+        },
+        _ => None,
+    }
+    .filter(|&field| field < fields.len());
+    let (start, width) = match field {
+        Some(field) => (
+            fields[..field]
+                .iter()
+                .map(|value| display_width(value) + 1)
+                .sum(),
+            display_width(fields[field]),
+        ),
+        None => (0, display_width(line)),
+    };
+
+    let gutter = " ".repeat(line_number.to_string().len());
+    Some(format!(
+        "{gutter}--> {}:{line_number}\n{gutter} |\n{line_number} | {line}\n{gutter} | {}{}\n",
+        path.display(),
+        " ".repeat(start),
+        "^".repeat(width.max(1))
+    ))
+}
+
+/// Whether `value` can be read into the column named `header`. Values of
+/// unknown columns are always valid.
+fn is_valid_field(header: &str, value: &str) -> bool {
+    match header {
+        "date" => value.parse::<NaiveDate>().is_ok(),
+        "amount" => value.parse::<Decimal>().is_ok(),
+        "amount_cents" => value.parse::<i64>().is_ok(),
+        "full_amount" => value.is_empty() || value.parse::<Decimal>().is_ok(),
+        "full_amount_cents" => value.is_empty() || value.parse::<i64>().is_ok(),
+        _ => true,
+    }
+}
+
+/// Number of reads by [`entries_from_file_retrying`] before giving up.
+const READ_ATTEMPTS: u32 = 3;
+const READ_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Same as [`entries_from_file`], but retries a few times while the file is
+/// missing or inaccessible, e.g. in the middle of an atomic replacement.
+pub fn entries_from_file_retrying(path: &Path) -> Result<Vec<Entry>, AppError> {
+    let mut attempt = 1;
+    loop {
+        match entries_from_file(path) {
+            Err(err) if err.is_transient() && attempt < READ_ATTEMPTS => {
+                attempt += 1;
+                std::thread::sleep(READ_RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Same as [`entries_from_file`], but reads a file separated by `delimiter`.
+pub fn entries_from_file_with_delimiter(
+    path: &Path,
+    delimiter: u8,
+) -> Result<Vec<Entry>, AppError> {
+    std::fs::metadata(path).map_err(|e| AppError::Io {
+        source: e,
+        context: format!("Failed to access file: {}", path.display()),
+    })?;
+
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(COMMENT))
+        .from_path(path)
+        .map_err(|source| AppError::Csv { source })?;
+    let entries = match AmountFormat::from_headers(reader.headers()?) {
+        AmountFormat::Decimal => reader
+            .deserialize::<Entry>()
+            .collect::<Result<Vec<_>, _>>()?,
+        AmountFormat::Cents => reader
+            .deserialize::<CentsEntry>()
+            .map(|entry| entry.map(Entry::from))
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    Ok(entries)
+}
+
+/// Expands a `YY-MM-DD` date with a two-digit year to `YYYY-MM-DD`: years
+/// below `pivot` are in the 2000s, the rest in the 1900s. Returns `None` for
+/// dates in any other form.
+pub fn expand_two_digit_year(date: &str, pivot: u32) -> Option<String> {
+    let (year, month_day) = date.split_once('-')?;
+    if year.len() != 2 || !year.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let year: u32 = year.parse().ok()?;
+    let century = if year < pivot { 2000 } else { 1900 };
+    Some(format!("{}-{month_day}", century + year))
+}
+
+/// Fixes for legacy or foreign files, applied to the fields of each row before
+/// it is read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Expands two-digit years with this pivot, see [`expand_two_digit_year`].
+    pub two_digit_year_pivot: Option<u32>,
+    /// Reads an empty amount as zero instead of failing, e.g. for the
+    /// informational rows of some exports.
+    pub blank_amount_as_zero: bool,
+}
+
+/// Same as [`entries_from_file_with_delimiter`], but with the fixes of
+/// `options` applied first.
+pub fn entries_from_file_with_options(
+    path: &Path,
+    delimiter: u8,
+    options: ReadOptions,
+) -> Result<Vec<Entry>, AppError> {
+    if options == ReadOptions::default() {
+        return entries_from_file_with_delimiter(path, delimiter);
+    }
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(COMMENT))
+        .from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let amount_format = AmountFormat::from_headers(&headers);
+    let date_index = headers.iter().position(|header| header == "date");
+    let amount_index = headers
+        .iter()
+        .position(|header| header == "amount" || header == "amount_cents");
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let mut fields: Vec<String> = record.iter().map(String::from).collect();
+        if let (Some(pivot), Some(date_index)) = (options.two_digit_year_pivot, date_index)
+            && let Some(date) = fields
+                .get(date_index)
+                .and_then(|date| expand_two_digit_year(date, pivot))
+        {
+            fields[date_index] = date;
+        }
+        if options.blank_amount_as_zero
+            && let Some(amount_index) = amount_index
+            && fields
+                .get(amount_index)
+                .is_some_and(|amount| amount.trim().is_empty())
+        {
+            fields[amount_index] = String::from("0");
+        }
+        let mut fixed: csv::StringRecord = fields.into_iter().collect();
+        fixed.set_position(record.position().cloned());
+        entries.push(match amount_format {
+            AmountFormat::Decimal => fixed.deserialize::<Entry>(Some(&headers))?,
+            AmountFormat::Cents => fixed
+                .deserialize::<CentsEntry>(Some(&headers))
+                .map(Entry::from)?,
+        });
+    }
+    Ok(entries)
+}
+
+/// A problem with a row of a CSV file that keeps it from being read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// Line of the row, unless the problem is with the whole file.
+    pub line: Option<u64>,
+    pub reason: String,
+}
+
+impl From<csv::Error> for RowError {
+    fn from(err: csv::Error) -> Self {
+        let line = err.position().map(|position| position.line());
+        let reason = match err.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+            _ => err.to_string(),
+        };
+        RowError { line, reason }
+    }
+}
+
+/// New entries read from a batch file, see [`read_batch`].
+pub struct Batch {
+    pub entries: Vec<Entry>,
+    pub errors: Vec<RowError>,
+}
+
+/// Reads new entries from a file with one `[date] amount` per line, e.g.
+/// `2024-10-01 -20.50`, or just `-20.50` for an entry on `default_date`.
+/// Blank lines and `#` comments are skipped, lines that can't be read are
+/// collected as errors.
+pub fn read_batch(path: &Path, default_date: NaiveDate) -> Result<Batch, AppError> {
+    let content = std::fs::read_to_string(path).map_err(|source| AppError::Io {
+        source,
+        context: format!("Failed to read batch file: {}", path.display()),
+    })?;
+    let mut batch = Batch {
+        entries: Vec::new(),
+        errors: Vec::new(),
+    };
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(COMMENT as char) {
+            continue;
+        }
+        match parse_batch_line(line, default_date) {
+            Ok(entry) => batch.entries.push(entry),
+            Err(err) => batch.errors.push(RowError {
+                line: Some(index as u64 + 1),
+                reason: err.to_string(),
+            }),
+        }
+    }
+    Ok(batch)
+}
+
+fn parse_batch_line(line: &str, default_date: NaiveDate) -> Result<Entry, AppError> {
+    // A leading date has a dash inside of it, an amount only in front
+    let (date, amount) = match line.split_once(char::is_whitespace) {
+        Some((date, amount)) if date.find('-').is_some_and(|index| index > 0) => {
+            let date = date.parse().map_err(|source| AppError::DateParse {
+                source,
+                input: date.to_string(),
+            })?;
+            (date, amount.trim())
+        }
+        _ => (default_date, line),
+    };
+    Ok(Entry {
+        date,
+        amount: parse_amount(amount)?,
+        full_amount: None,
+    })
+}
+
+/// Checks every row of the file, collecting all problems instead of stopping
+/// at the first one like [`entries_from_file`] does.
+pub fn validate_file(path: &Path) -> Vec<RowError> {
+    let mut reader = match ReaderBuilder::new()
+        .delimiter(DELIMITER)
+        .comment(Some(COMMENT))
+        .from_path(path)
+    {
+        Ok(reader) => reader,
+        Err(err) => return vec![err.into()],
+    };
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => return vec![err.into()],
+    };
+    let amount_format = AmountFormat::from_headers(&headers);
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let result = record.and_then(|record| match amount_format {
+            AmountFormat::Decimal => record.deserialize::<Entry>(Some(&headers)).map(drop),
+            AmountFormat::Cents => record.deserialize::<CentsEntry>(Some(&headers)).map(drop),
+        });
+        if let Err(err) = result {
+            errors.push(err.into());
+        }
+    }
+    errors
+}
+
+/// Each CSV file of a directory with the problems found in it.
+pub type DirectoryValidation = Vec<(PathBuf, Vec<RowError>)>;
+
+/// Validates every CSV file in the directory, see [`validate_file`]. Files
+/// without problems have no errors.
+pub fn validate_directory(dir: &Path) -> Result<DirectoryValidation, Box<dyn std::error::Error>> {
+    Ok(get_csv_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let errors = validate_file(&path);
+            (path, errors)
+        })
+        .collect())
+}
+
+/// Each CSV file of a directory with the total of its entries.
+pub type DirectorySummary = Vec<(PathBuf, Decimal)>;
+
+/// Totals every CSV file in the directory. Files without entries total zero.
+pub fn summarize_directory(dir: &Path) -> Result<DirectorySummary, Box<dyn std::error::Error>> {
+    get_csv_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let total = entries_from_file(&path)?
+                .iter()
+                .map(|entry| entry.amount)
+                .sum();
+            Ok((path, total))
+        })
+        .collect()
+}
+
+/// The CSV files at `path`: the file itself when it is one, otherwise the
+/// CSV files of the directory, see [`get_csv_files`].
+pub fn csv_files_at(path: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if path.is_file() {
+        Ok(vec![path.to_path_buf()])
+    } else {
+        get_csv_files(path)
+    }
+}
+
+pub fn get_csv_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()?.eq_ignore_ascii_case("csv") {
+                Some(path)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use number_formatter::CurrencyPosition;
+    use std::str::FromStr;
+
+    fn report() -> Report {
+        Report {
+            filter: Some(String::from("2024")),
+            exclude_filter: None,
+            fill_gaps: false,
+            entries: vec![
+                Entry {
+                    date: NaiveDate::from_ymd_opt(2024, 9, 11).unwrap(),
+                    amount: Decimal::from(700),
+                    full_amount: None,
+                },
+                Entry {
+                    date: NaiveDate::from_ymd_opt(2024, 10, 2).unwrap(),
+                    amount: Decimal::from_str("-3000.42").unwrap(),
+                    full_amount: None,
+                },
+            ],
+        }
+    }
+
+    fn format_options() -> FormatOptions {
+        FormatOptions {
+            thousands_separator: ' ',
+            decimal_separator: '.',
+            currency: CurrencyPosition::None,
+            pad_integer_digits: None,
+        }
+    }
+
+    #[test]
+    fn test_report_to_json() {
+        let json = report().to_json(&format_options());
+        insta::assert_snapshot!(serde_json::to_string_pretty(&json).unwrap(), @r#"
+        {
+          "entries": [
+            {
+              "amount": "700",
+              "date": "2024-09-11",
+              "formatted_amount": "700.00"
+            },
+            {
+              "amount": "-3000.42",
+              "date": "2024-10-02",
+              "formatted_amount": "-3 000.42"
+            }
+          ],
+          "filter": "2024",
+          "formatted_total": "-2 300.42",
+          "total": "-2300.42"
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_report_to_csv() {
+        insta::assert_snapshot!(report().to_csv(b','), @r"
+        date,amount
+        2024-09-11,700
+        2024-10-02,-3000.42
+        ");
+    }
+
+    #[test]
+    fn test_quarter_label() {
+        let label = |date: &str| Period::Quarter.label(date.parse().unwrap());
+        assert_eq!(label("2024-01-01"), "2024-Q1");
+        assert_eq!(label("2024-03-31"), "2024-Q1");
+        assert_eq!(label("2024-04-01"), "2024-Q2");
+        assert_eq!(label("2024-09-30"), "2024-Q3");
+        assert_eq!(label("2024-12-31"), "2024-Q4");
+    }
+
+    #[test]
+    fn test_reconcile_rounding() {
+        let amounts = ["0.005", "0.005", "0.005"].map(|amount| Decimal::from_str(amount).unwrap());
+        assert_eq!(
+            reconcile_rounding(&amounts),
+            ["0.01", "0.01", "0.00"].map(|amount| Decimal::from_str(amount).unwrap())
+        );
+
+        let amounts = ["-1.004", "2.336", "10"].map(|amount| Decimal::from_str(amount).unwrap());
+        assert_eq!(
+            reconcile_rounding(&amounts),
+            ["-1.00", "2.33", "10"].map(|amount| Decimal::from_str(amount).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_running_balances() {
+        assert_eq!(running_balances(&[]).count(), 0);
+
+        let entries = report().entries;
+        let balances: Vec<Decimal> = running_balances(&entries)
+            .map(|(_, balance)| balance)
+            .collect();
+        assert_eq!(
+            balances,
+            vec![Decimal::from(700), Decimal::from_str("-2300.42").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_period_deltas() {
+        let group = |total: i64| PeriodTotal {
+            period: String::new(),
+            total: Decimal::from(total),
+            count: 1,
+        };
+        let deltas = period_deltas(&[group(-200), group(-300), group(0), group(50)]);
+        assert_eq!(
+            deltas,
+            vec![
+                None,
+                Some(PeriodDelta {
+                    change: Decimal::from(-100),
+                    percent: Some(Decimal::from(-50)),
+                }),
+                Some(PeriodDelta {
+                    change: Decimal::from(300),
+                    percent: Some(Decimal::from(100)),
+                }),
+                Some(PeriodDelta {
+                    change: Decimal::from(50),
+                    percent: None,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_period_next_start() {
+        let date = NaiveDate::from_ymd_opt(2024, 11, 30).unwrap();
+        let next_start = |period: Period| period.next_start(date).to_string();
+        assert_eq!(next_start(Period::Day), "2024-12-01");
+        assert_eq!(next_start(Period::Month), "2024-12-01");
+        assert_eq!(next_start(Period::Quarter), "2025-01-01");
+        assert_eq!(next_start(Period::Year), "2025-01-01");
+    }
+
+    #[test]
+    fn test_display_width_counts_terminal_columns() {
+        assert_eq!(display_width("12.00"), 5);
+        assert_eq!(display_width("12.00円"), 7);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(pad_left("食費", 6), "  食費");
+        assert_eq!(pad_left("too wide", 3), "too wide");
+    }
+
+    #[test]
+    fn test_monthly_dates_clamp_to_month_end() {
+        let date = |date: &str| date.parse::<NaiveDate>().unwrap();
+        assert_eq!(
+            monthly_dates(date("2024-01-01"), date("2024-04-01"), 31),
+            [
+                date("2024-01-31"),
+                date("2024-02-29"),
+                date("2024-03-31"),
+                date("2024-04-30"),
+            ]
+        );
+        assert_eq!(
+            monthly_dates(date("2024-12-01"), date("2025-01-01"), 15),
+            [date("2024-12-15"), date("2025-01-15")]
+        );
+        assert!(monthly_dates(date("2025-01-01"), date("2024-12-01"), 1).is_empty());
+    }
+
+    #[test]
+    fn test_expand_two_digit_year() {
+        assert_eq!(
+            expand_two_digit_year("00-01-02", 70).as_deref(),
+            Some("2000-01-02")
+        );
+        assert_eq!(
+            expand_two_digit_year("69-12-31", 70).as_deref(),
+            Some("2069-12-31")
+        );
+        assert_eq!(
+            expand_two_digit_year("70-01-01", 70).as_deref(),
+            Some("1970-01-01")
+        );
+        assert_eq!(
+            expand_two_digit_year("99-10-01", 70).as_deref(),
+            Some("1999-10-01")
+        );
+        assert_eq!(
+            expand_two_digit_year("24-10-01", 0).as_deref(),
+            Some("1924-10-01")
+        );
+        assert_eq!(expand_two_digit_year("2024-10-01", 70), None);
+        assert_eq!(expand_two_digit_year("x4-10-01", 70), None);
+    }
+
+    #[test]
+    fn test_report_to_ledger() {
+        insta::assert_snapshot!(report().to_ledger("expenses"), @r"
+        2024-09-11 expenses
+            Assets:expenses  700.00
+            Equity:Unassigned
+
+        2024-10-02 expenses
+            Assets:expenses  -3000.42
+            Equity:Unassigned
+        ");
+    }
+
+    fn entry(date: &str, amount: &str) -> Entry {
+        Entry {
+            date: date.parse().unwrap(),
+            amount: Decimal::from_str(amount).unwrap(),
+            full_amount: None,
+        }
+    }
+
+    #[test]
+    fn test_entry_equality() {
+        assert_eq!(entry("2024-09-11", "700"), entry("2024-09-11", "700.00"));
+        assert_ne!(entry("2024-09-11", "700"), entry("2024-09-12", "700"));
+        assert_ne!(entry("2024-09-11", "700"), entry("2024-09-11", "-700"));
+    }
+
+    #[test]
+    fn test_entry_ordering() {
+        let mut entries = vec![
+            entry("2024-10-02", "5"),
+            entry("2024-09-11", "700"),
+            entry("2024-10-02", "-5"),
+        ];
+        entries.sort();
+        assert_eq!(
+            entries,
+            [
+                entry("2024-09-11", "700"),
+                entry("2024-10-02", "-5"),
+                entry("2024-10-02", "5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("-999.99").unwrap(), Decimal::new(-99999, 2));
+        assert_eq!(parse_amount("1_000_000").unwrap(), Decimal::from(1_000_000));
+        assert_eq!(
+            parse_amount("1 000 000.50").unwrap(),
+            Decimal::new(100000050, 2)
+        );
+        assert_eq!(parse_amount("-12_345.6").unwrap(), Decimal::new(-123456, 1));
+    }
+
+    #[test]
+    fn test_evaluate_amount() {
+        let evaluate = |input| evaluate_amount(input).unwrap().to_string();
+        assert_eq!(evaluate("-1 000.50"), "-1000.50");
+        assert_eq!(evaluate("12 + 3"), "15");
+        assert_eq!(evaluate("2 + 3 * 4 - 1"), "13");
+        assert_eq!(evaluate("-(2 + 3) * 1_000"), "-5000");
+        assert_eq!(evaluate("100 / 3"), "33.33");
+        assert_eq!(evaluate("120 / 7 * 2"), "34.29");
+        assert_eq!(evaluate("1.234"), "1.234");
+        for input in ["", "12 +", "(1 + 2", "1 / 0", "1 + + 2", "2 3 .5", "1,5"] {
+            assert!(
+                evaluate_amount(input).is_err(),
+                "{input:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_amount_rejects_malformed_input() {
+        for input in [
+            "", "_1", "1_", "1__000", "1 _000", "-_1", "1_.5", "1._5", "1,5", "abc",
+        ] {
+            assert!(parse_amount(input).is_err(), "{input:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_entries_from_file_retrying_waits_for_file() {
+        let tempdir = temp_dir::TempDir::with_prefix("mfinance-lib-test-").unwrap();
+        let path = tempdir.child("test.csv");
+        let tmp_path = tempdir.child("test.csv.tmp");
+        std::fs::write(&tmp_path, "date;amount\n2024-09-11;700\n").unwrap();
+        // The file appears shortly after the first read, like after an atomic rename
+        let writer = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                std::fs::rename(tmp_path, path).unwrap();
+            }
+        });
+
+        let entries = entries_from_file_retrying(&path).unwrap();
+        writer.join().unwrap();
+        assert_eq!(entries, [entry("2024-09-11", "700")]);
+    }
+
+    #[test]
+    fn test_entries_from_file_retrying_gives_up() {
+        let tempdir = temp_dir::TempDir::with_prefix("mfinance-lib-test-").unwrap();
+        let result = entries_from_file_retrying(&tempdir.child("missing.csv"));
+        assert!(matches!(result, Err(AppError::Io { .. })));
+    }
+
+    #[test]
+    fn test_get_csv_files_ignores_extension_case() {
+        let tempdir = temp_dir::TempDir::with_prefix("mfinance-lib-test-").unwrap();
+        for name in ["b.CSV", "a.csv", "c.Csv", "notes.txt", "csv"] {
+            std::fs::write(tempdir.child(name), "").unwrap();
+        }
+
+        let names: Vec<String> = get_csv_files(tempdir.path())
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["a.csv", "b.CSV", "c.Csv"]);
+    }
 }
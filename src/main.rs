@@ -1,14 +1,25 @@
 use chrono::NaiveDate;
-use clap::{Parser, Subcommand};
-use csv::WriterBuilder;
+use chrono_tz::Tz;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use directories::ProjectDirs;
 use rust_decimal::Decimal;
-use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
+use mfinance::clock::{Clock, FixedClock, SystemClock, TimezoneClock};
 use mfinance::config;
+use mfinance::number_formatter::{FormatOptions, NumberFormatter};
 use mfinance::tui;
-use mfinance::{AppError, add_entry, entries_from_file, generate_report, generate_report_for_all};
+use mfinance::{
+    AmountColumn, AmountFormat, AppError, DELIMITER, DayKind, Entry, FileLock, Period, ReadOptions,
+    Report, ReturnInfo, Sign, SortKey, amount_format_of_file, amount_format_of_file_with_delimiter,
+    append_entries, archive_entries, backup_file, checksum, display_width, entries_from_file,
+    entries_from_file_with_options, generate_report_for_all, generate_report_for_all_with_options,
+    generate_report_with_options, group_by_period, lock_file, migrate_entries, monthly_dates,
+    pad_left, parse_amount, read_batch, read_report_marker, sample_entries, stamp_comment,
+    write_entries, write_entries_with_delimiter, write_report_marker,
+};
 
 #[derive(Parser)]
 #[command(name = "mfinance")]
@@ -16,23 +27,146 @@ use mfinance::{AppError, add_entry, entries_from_file, generate_report, generate
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Print how long each step of the command took to stderr
+    #[arg(long, global = true, hide = true)]
+    profile: bool,
+    /// Take the default separators from the locale in LC_ALL, LC_NUMERIC or LANG
+    ///
+    /// Separators set in a config file still take precedence.
+    #[arg(long, global = true)]
+    locale_from_env: bool,
+    /// On a CSV error, print the offending line with the bad field underlined
+    #[arg(long, global = true)]
+    pretty_errors: bool,
+    /// Resolve today's date in this IANA timezone, e.g. `Europe/Berlin` or
+    /// `UTC`, instead of in the system timezone
+    #[arg(long, global = true, value_name = "TIMEZONE", value_parser = parse_timezone)]
+    tz: Option<Tz>,
+    /// Copy a file to `<file>.bak` before changing it, like `enabled` in the
+    /// `[backup]` config section
+    #[arg(long, global = true)]
+    backup: bool,
+    /// Which config file wins where both set the same key
+    #[arg(long, global = true, value_enum, default_value_t = ConfigPrecedence::Project)]
+    config_precedence: ConfigPrecedence,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ConfigPrecedence {
+    /// The global config overrides the local one of the data directory
+    Global,
+    /// The local config of the data directory overrides the global one
+    Project,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
+    Markdown,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print only the effective settings, in TOML
+    Show {
+        /// CSV file or data directory whose local `mfinance.toml` should apply
+        path: Option<PathBuf>,
+    },
+    /// Print only the config files in use
+    Path {
+        /// CSV file or data directory whose local `mfinance.toml` should apply
+        path: Option<PathBuf>,
+    },
+    /// Print a sample amount formatted with the effective settings
+    Sample {
+        /// CSV file or data directory whose local `mfinance.toml` should apply
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// Journal for plain-text accounting tools like ledger and hledger
+    Ledger,
+}
+
+/// Measures consecutive steps of a command for `--profile`.
+struct Profiler {
+    enabled: bool,
+    step_start: Instant,
+}
+
+impl Profiler {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            step_start: Instant::now(),
+        }
+    }
+
+    /// Reports the time since the previous step and starts the next one.
+    fn step(&mut self, name: &str) {
+        if self.enabled {
+            eprintln!("profile: {name}: {:?}", self.step_start.elapsed());
+        }
+        self.step_start = Instant::now();
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Interactive terminal UI
     Tui {
-        /// Directory containing CSV files
+        /// Directory containing CSV files, or a single CSV file
         path: PathBuf,
+        /// Start with the file whose name best matches this query (e.g. `exp`)
+        #[arg(short, long)]
+        select: Option<String>,
+        /// Start with the file of exactly this name (e.g. `expenses.csv`)
+        #[arg(long, conflicts_with = "select")]
+        file: Option<String>,
     },
     /// Add a new entry with amount to the CSV file
     NewEntry {
-        /// Amount to add (e.g. -999.99)
-        #[arg(short, long, allow_negative_numbers = true)]
-        amount: Decimal,
-        /// Date of the entry (e.g. 2024-12-12, defaults to today)
+        /// Amount to add (e.g. -999.99 or 1_000_000), or `-` to read it from
+        /// the first line of stdin
+        #[arg(short, long, allow_hyphen_values = true, value_parser = parse_amount_arg, required_unless_present = "batch", conflicts_with = "batch")]
+        amount: Option<AmountArg>,
+        /// Date of the entry (e.g. 2024-12-12, defaults to today), or of the
+        /// batch lines without a date
         #[arg(short, long)]
         date: Option<String>,
+        /// Add an entry for every `[date] amount` line of this file, e.g.
+        /// `2024-12-12 -999.99`
+        #[arg(long, value_name = "PATH")]
+        batch: Option<PathBuf>,
+        /// Add nothing when a line of the batch can't be read
+        #[arg(long, requires = "batch")]
+        strict: bool,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Add the same entry on a day of every month in a range, e.g. for rent
+    Recur {
+        /// Amount of each entry (e.g. -950 or 1_000)
+        #[arg(short, long, allow_hyphen_values = true, value_parser = parse_amount)]
+        amount: Decimal,
+        /// First month to add an entry in (e.g. 2025-01)
+        #[arg(long, value_name = "MONTH")]
+        from: String,
+        /// Last month to add an entry in (e.g. 2025-12)
+        #[arg(long, value_name = "MONTH")]
+        to: String,
+        /// Day of the month, the last day for months that are shorter
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=31))]
+        day: u32,
         /// Path to the CSV file
         file: PathBuf,
     },
@@ -47,94 +181,939 @@ enum Commands {
         /// - To filter entries for a specific month, use `2024-02`.
         #[arg(short, long)]
         filter: Option<String>,
+        /// Leave out entries whose date starts with this prefix, e.g. `2025`
+        /// for all years but 2025; applied after `--filter`
+        #[arg(long, value_name = "PREFIX")]
+        exclude_filter: Option<String>,
+        /// Only show entries dated after the last report made with `--mark`
+        #[arg(long)]
+        new_only: bool,
+        /// Only show entries dated on or before this date, e.g. `2024-06-30`,
+        /// so that the total is the balance as of that date
+        #[arg(long, value_name = "DATE")]
+        as_of: Option<String>,
+        /// Only show entries dated strictly after this date, e.g. `2024-06-30`
+        #[arg(long, value_name = "DATE")]
+        after: Option<String>,
+        /// Only show entries dated strictly before this date, e.g. `2024-07-01`
+        #[arg(long, value_name = "DATE")]
+        before: Option<String>,
+        /// Remember the latest reported date for a later `--new-only` report
+        #[arg(long)]
+        mark: bool,
+        /// Print a zero total instead of failing when there are no entries
+        ///
+        /// Overrides `empty_result` from the config.
+        #[arg(long)]
+        allow_empty: bool,
+        /// Fail when there are no entries, even if the config sets `empty_result = "ok"`
+        #[arg(long, conflicts_with = "allow_empty")]
+        fail_on_empty: bool,
+        /// Print per-period subtotals as `period;total` CSV instead of the entries
+        #[arg(long, value_name = "PERIOD", group = "grouping")]
+        csv_summary: Option<Period>,
+        /// Append a `TOTAL` row with the grand total to the CSV summary
+        #[arg(long, requires = "csv_summary")]
+        summary_total: bool,
+        /// Print only the entry rows, without the total line
+        #[arg(long, conflicts_with = "csv_summary")]
+        no_total: bool,
+        /// Print the rows to stderr and only the bare total to stdout, e.g.
+        /// for `total=$(mfinance report --total-to-stdout finances.csv)`
+        #[arg(long, conflicts_with_all = ["csv_summary", "no_total", "format", "net_by_file"])]
+        total_to_stdout: bool,
+        /// Show only the N entries with the largest absolute amounts
+        #[arg(long, value_name = "N", conflicts_with = "csv_summary")]
+        top: Option<usize>,
+        /// Prefix each entry with its 1-based position in the report
+        #[arg(long, conflicts_with = "csv_summary")]
+        with_indices: bool,
+        /// Show a subtotal per period instead of the entries
+        #[arg(long, value_name = "PERIOD", group = "grouping", conflicts_with_all = ["csv_summary", "top", "with_indices"])]
+        group_by: Option<Period>,
+        /// Include a zero subtotal for every period without entries between
+        /// the first and the last one, e.g. for charting
+        #[arg(long, requires = "grouping")]
+        fill_gaps: bool,
+        /// Show the change of each `--group-by` subtotal versus the previous
+        /// period, absolute and in percent (text and json only)
+        #[arg(long, requires = "group_by")]
+        deltas: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text, conflicts_with_all = ["csv_summary", "no_total", "top", "with_indices"])]
+        format: ReportFormat,
+        /// Print a title line above the report, e.g. "October Expenses"
+        #[arg(long, conflicts_with_all = ["csv_summary", "format"])]
+        title: Option<String>,
+        /// Only show entries with negative amounts, e.g. expenses
+        #[arg(long, conflicts_with = "positives")]
+        negatives: bool,
+        /// Only show entries with positive amounts, e.g. income
+        #[arg(long)]
+        positives: bool,
+        /// Leave out entries dated on a Saturday or a Sunday
+        #[arg(long, conflicts_with = "weekends_only")]
+        exclude_weekends: bool,
+        /// Only show entries dated on a Saturday or a Sunday
+        #[arg(long)]
+        weekends_only: bool,
+        /// Show the entries ordered by date or by amount, the file stays as is
+        #[arg(long, value_name = "KEY", conflicts_with_all = ["csv_summary", "top", "group_by", "format", "opening_balance", "running_min_max"])]
+        sort_output: Option<SortKey>,
+        /// Order `--sort-output` from the latest date or the largest amount
+        #[arg(long, requires = "sort_output")]
+        desc: bool,
+        /// Show the amounts as percentages, e.g. of an allocation plan, and
+        /// check that they sum to 100
+        #[arg(long, conflicts_with_all = ["csv_summary", "format", "opening_balance", "running_min_max"])]
+        percent: bool,
+        /// Amount column to report, e.g. `full-amount` for whole shared expenses
+        #[arg(long, value_enum, default_value_t = AmountColumn::Amount)]
+        column: AmountColumn,
+        /// Show the running balance after each entry, starting from this
+        /// balance, and include it in the total
+        #[arg(long, value_name = "BALANCE", allow_hyphen_values = true, value_parser = parse_amount, conflicts_with_all = ["csv_summary", "top", "group_by", "format"])]
+        opening_balance: Option<Decimal>,
+        /// Show the running balance with the lowest and the highest balance
+        /// reached so far after each entry
+        #[arg(long, conflicts_with_all = ["csv_summary", "top", "group_by", "format"])]
+        running_min_max: bool,
+        /// Treat the path as a directory and print the net total of each CSV
+        /// file in it, followed by the combined total
+        #[arg(long, conflicts_with_all = ["filter", "exclude_filter", "new_only", "as_of", "after", "before", "mark", "csv_summary", "top", "with_indices", "group_by", "format", "title", "negatives", "positives", "exclude_weekends", "weekends_only", "opening_balance", "running_min_max", "sort_output"])]
+        net_by_file: bool,
+        /// Sort the files of `--net-by-file` by their total, largest first
+        #[arg(long, requires = "net_by_file")]
+        sort: bool,
+        /// Read an empty amount as zero instead of failing, e.g. for the
+        /// informational rows of some exports
+        #[arg(long)]
+        blank_as_zero: bool,
         /// Path to the CSV file
         file: PathBuf,
     },
     /// Sort the entries in the CSV file by date
     Sort {
+        /// Field delimiter of the CSV file being read
+        #[arg(long, default_value = ";", value_parser = parse_delimiter)]
+        separator: u8,
+        /// Field delimiter to write the sorted entries with (e.g. `,` to
+        /// convert them), only into a separate `--output` file as other
+        /// commands read `;`
+        #[arg(long, value_parser = parse_delimiter, requires = "output")]
+        separator_output: Option<u8>,
+        /// Write the sorted entries to this file instead of back to the CSV file
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Start the file with a comment saying when and by what it was generated
+        #[arg(long)]
+        stamp: bool,
+        /// Expand two-digit years like `24-10-01`: years below the pivot are
+        /// in the 2000s, the rest in the 1900s
+        #[arg(long, value_name = "PIVOT")]
+        two_digit_year_pivot: Option<u32>,
+        /// Read an empty amount as zero and write it as `0`
+        #[arg(long)]
+        blank_as_zero: bool,
         /// Path to the CSV file
         file: PathBuf,
     },
+    /// Move entries older than a cutoff date into an archive file
+    Archive {
+        /// Entries dated before this date are archived (e.g. 2024-01-01)
+        #[arg(short, long)]
+        before: String,
+        /// Path to the archive CSV file, created if it doesn't exist
+        #[arg(short, long)]
+        into: PathBuf,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Write a copy of the CSV file with every amount multiplied by a fixed rate
+    Convert {
+        /// Exchange rate to multiply amounts by (e.g. 0.92)
+        #[arg(short, long)]
+        rate: Decimal,
+        /// Path to the converted CSV file
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Start the file with a comment saying when and by what it was generated
+        #[arg(long)]
+        stamp: bool,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Rewrite the CSV file with amounts stored as decimals or integer cents
+    Migrate {
+        /// How amounts are stored after the migration
+        #[arg(long, value_enum)]
+        to: AmountFormat,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Print a checksum of the entries, the same for files with the same
+    /// entries however they're formatted
+    Checksum {
+        /// Files with the same entries in a different order get different checksums
+        #[arg(long)]
+        ordered: bool,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// List every distinct date with the number of entries on it
+    Dates {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Check every CSV file in a directory for rows that can't be read
+    Doctor {
+        /// Directory containing CSV files
+        path: PathBuf,
+    },
+    /// Compute the simple rate of return of the entries as contributions
+    Return {
+        /// Balance the contributions grew into, e.g. the current account value
+        #[arg(long = "final", value_name = "BALANCE", allow_hyphen_values = true, value_parser = parse_amount)]
+        final_balance: Decimal,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Print the entries in a format of another tool
+    Export {
+        /// Export format
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Print the config files in use and the effective settings
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+        /// CSV file or data directory whose local `mfinance.toml` should apply
+        path: Option<PathBuf>,
+    },
+    /// Write a CSV file with a few example entries to try the other commands on
+    Init {
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+        /// Path to the CSV file to create
+        file: PathBuf,
+    },
+    /// Print a shell completion script, e.g. into a bash completions directory
+    #[command(hide = true)]
+    Completions {
+        /// Shell to complete commands in
+        shell: clap_complete::Shell,
+    },
 }
 
 fn main() -> Result<(), main_error::MainError> {
     let cli = Cli::parse();
+    if let Err(err) = check_conflicts(&cli) {
+        err.exit();
+    }
+    // The command is consumed by `run`, so remember the file an error may point into
+    let error_file = cli.pretty_errors.then(|| csv_file(&cli.command)).flatten();
 
-    let config = match load_config(&cli) {
+    let err = match run(cli) {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+    let snippet = error_file.and_then(|(file, delimiter)| {
+        mfinance::error_snippet(&file, err.downcast_ref::<AppError>()?, delimiter)
+    });
+    match snippet {
+        Some(snippet) => {
+            // Report the error the way returning it would, followed by the snippet
+            eprintln!("Error: {:?}", main_error::MainError::from(err));
+            eprint!("{snippet}");
+            std::process::exit(1);
+        }
+        None => Err(err.into()),
+    }
+}
+
+/// Rejects argument combinations that clap can't express, e.g. a flag that
+/// conflicts with only one value of another option.
+fn check_conflicts(cli: &Cli) -> Result<(), clap::Error> {
+    if let Commands::Report {
+        deltas: true,
+        format: ReportFormat::Markdown,
+        ..
+    } = cli.command
+    {
+        return Err(Cli::command().error(
+            clap::error::ErrorKind::ArgumentConflict,
+            "--deltas can't be used with --format markdown",
+        ));
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let global_config = global_config_path();
+    let data_config = data_config_path(&cli);
+    let locale_separators = if cli.locale_from_env {
+        let locale = numeric_locale().unwrap_or_default();
+        let separators = config::locale_separators(&locale);
+        if separators.is_none() {
+            eprintln!("Warning: Unknown number format of locale {locale:?}");
+        }
+        separators
+    } else {
+        None
+    };
+    let mut config = match load_config(
+        global_config.as_deref(),
+        data_config.as_deref(),
+        locale_separators,
+        cli.config_precedence,
+    ) {
         Ok(config) => config,
         Err(e) => {
             eprintln!("Warning: Failed to load config: {e}");
             config::Config::default()
         }
     };
+    for warning in config.keys.conflicts() {
+        eprintln!("Warning: {warning}");
+    }
+    if let Some(warning) = config.formatting.warning() {
+        eprintln!("Warning: {warning}");
+    }
+    if let Some(warning) = config.layout.warning() {
+        eprintln!("Warning: {warning}");
+    }
+    config.backup.enabled |= cli.backup;
     let format_options = config.formatting.format_options();
+    let clock = clock(cli.tz)?;
+    let mut profiler = Profiler::new(cli.profile);
+    profiler.step("config");
 
     match cli.command {
-        Commands::NewEntry { amount, date, file } => {
+        Commands::NewEntry {
+            amount,
+            date,
+            batch,
+            strict,
+            file,
+        } => {
             let date: NaiveDate = if let Some(date) = date {
                 date.parse().map_err(|source| AppError::DateParse {
                     source,
                     input: date.clone(),
                 })?
             } else {
-                chrono::Local::now().date_naive()
+                clock.today()
+            };
+            match (amount, batch) {
+                (Some(amount), None) => {
+                    let amount = match amount {
+                        AmountArg::Value(amount) => amount,
+                        AmountArg::Stdin => read_stdin_amount()?,
+                    };
+                    let _lock = lock_file(&file)?;
+                    backup(&config.backup, &file)?;
+                    let entry = Entry {
+                        date,
+                        amount,
+                        full_amount: None,
+                    };
+                    let info = append_entries(&file, &[entry])?;
+                    print!("{}", info.display(format_options));
+                }
+                (None, Some(batch_path)) => {
+                    let batch = read_batch(&batch_path, date)?;
+                    for error in &batch.errors {
+                        let line = error.line.unwrap_or_default();
+                        eprintln!("{}:{line}: {}", batch_path.display(), error.reason);
+                    }
+                    // Without `--strict` the valid lines are added, unless there are none
+                    if !batch.errors.is_empty() && (strict || batch.entries.is_empty()) {
+                        return Err(AppError::InvalidRows(batch.errors.len()).into());
+                    }
+                    let _lock = lock_file(&file)?;
+                    backup(&config.backup, &file)?;
+                    let info = append_entries(&file, &batch.entries)?;
+                    println!("Added: {}", batch.entries.len());
+                    print!("{}", info.display(format_options));
+                }
+                _ => unreachable!("clap requires exactly one of --amount and --batch"),
+            }
+        }
+        Commands::Recur {
+            amount,
+            from,
+            to,
+            day,
+            file,
+        } => {
+            let month_start = |month: &String| {
+                format!("{month}-01")
+                    .parse::<NaiveDate>()
+                    .map_err(|source| AppError::DateParse {
+                        source,
+                        input: month.clone(),
+                    })
             };
-            let info = add_entry(&file, date, amount)?;
+            let entries: Vec<Entry> = monthly_dates(month_start(&from)?, month_start(&to)?, day)
+                .into_iter()
+                .map(|date| Entry {
+                    date,
+                    amount,
+                    full_amount: None,
+                })
+                .collect();
+            let _lock = lock_file(&file)?;
+            backup(&config.backup, &file)?;
+            let info = append_entries(&file, &entries)?;
+            println!("Added: {}", entries.len());
             print!("{}", info.display(format_options));
         }
-        Commands::Report { filter, file } => {
-            let report = if let Some(filter) = filter {
-                generate_report(&file, &filter)?
+        Commands::Report {
+            filter,
+            exclude_filter,
+            new_only,
+            as_of,
+            after,
+            before,
+            mark,
+            allow_empty,
+            fail_on_empty,
+            csv_summary,
+            summary_total,
+            no_total,
+            total_to_stdout,
+            top,
+            with_indices,
+            group_by,
+            fill_gaps,
+            deltas,
+            format,
+            title,
+            negatives,
+            positives,
+            exclude_weekends,
+            weekends_only,
+            sort_output,
+            desc,
+            percent,
+            column,
+            opening_balance,
+            running_min_max,
+            net_by_file,
+            sort,
+            blank_as_zero,
+            file,
+        } => {
+            if net_by_file {
+                print_net_by_file(&file, sort, &format_options)?;
+                return Ok(());
+            }
+            let options = ReadOptions {
+                blank_amount_as_zero: blank_as_zero,
+                ..ReadOptions::default()
+            };
+            let mut report = if let Some(filter) = filter.as_ref() {
+                generate_report_with_options(&file, filter, options)
             } else {
-                generate_report_for_all(&file)?
+                generate_report_for_all_with_options(&file, options)
             };
-            print!("{}", report.display(format_options));
+            if let Some(exclude_filter) = exclude_filter.as_ref() {
+                report = report.and_then(|report| report.excluding(exclude_filter));
+            }
+            if new_only && let Some(marker) = read_report_marker(&file)? {
+                report = report.and_then(|report| report.newer_than(marker));
+            }
+            let parse_date = |input: String| {
+                input
+                    .parse::<NaiveDate>()
+                    .map_err(|source| AppError::DateParse { source, input })
+            };
+            if let Some(as_of) = as_of {
+                let date = parse_date(as_of)?;
+                report = report.and_then(|report| report.as_of(date));
+            }
+            if after.is_some() || before.is_some() {
+                let after = after.map(parse_date).transpose()?;
+                let before = before.map(parse_date).transpose()?;
+                report = report.and_then(|report| report.between(after, before));
+            }
+            if exclude_weekends {
+                report = report.and_then(|report| report.on_days(DayKind::Weekday));
+            } else if weekends_only {
+                report = report.and_then(|report| report.on_days(DayKind::Weekend));
+            }
+            if negatives {
+                report = report.and_then(|report| report.with_sign(Sign::Negative));
+            } else if positives {
+                report = report.and_then(|report| report.with_sign(Sign::Positive));
+            }
+            let allow_empty =
+                allow_empty || (!fail_on_empty && config.empty_result == config::EmptyResult::Ok);
+            let report = match report {
+                Err(err) if allow_empty && err.is_no_entries() => Report::empty(filter),
+                report => report?,
+            }
+            .with_column(column)
+            .fill_gaps(fill_gaps);
+            // Reading, parsing and sorting happen in one pass over the file
+            profiler.step("read");
+            if let Some(period) = csv_summary {
+                print!("{}", report.to_csv_summary(period, summary_total)?);
+            } else if let ReportFormat::Json = format {
+                let json = match group_by {
+                    Some(period) => report.to_json_grouped(period, deltas, &format_options),
+                    None => report.to_json(&format_options),
+                };
+                println!("{}", serde_json::to_string_pretty(&json)?);
+            } else if let ReportFormat::Markdown = format {
+                print!("{}", report.to_markdown(group_by, &format_options));
+            } else {
+                let display = report
+                    .display(format_options)
+                    .with_total(!no_total && !total_to_stdout)
+                    .top(top)
+                    .with_indices(with_indices)
+                    .group_by(group_by)
+                    .title(title)
+                    .opening_balance(opening_balance)
+                    .running_min_max(running_min_max)
+                    .reconcile_rounding(config.formatting.reconcile_rounding)
+                    .sort_by(sort_output)
+                    .descending(desc)
+                    .percent(percent)
+                    .deltas(deltas);
+                if total_to_stdout {
+                    eprint!("{display}");
+                    // `{:.2}` alone truncates a total like 10.015
+                    println!("{:.2}", display.total().round_dp(2));
+                } else {
+                    print!("{display}");
+                }
+            }
+            profiler.step("output");
+            if mark {
+                write_report_marker(&file, &report)?;
+                profiler.step("mark");
+            }
         }
-        Commands::Tui { path } => {
-            let files = mfinance::get_csv_files(&path)?;
+        Commands::Tui { path, select, file } => {
+            let files = mfinance::csv_files_at(&path)?;
             if files.is_empty() {
-                return Err(main_error::MainError::from(AppError::Io {
+                return Err(AppError::Io {
                     source: std::io::Error::new(std::io::ErrorKind::NotFound, "No CSV files found"),
                     context: format!("No CSV files found in directory: {}", path.display()),
-                }));
+                }
+                .into());
             }
-            tui::run_tui(files, config)?;
-        }
-        Commands::Sort { file } => {
-            let mut entries = entries_from_file(&file)?;
-            entries.sort_by(|a, b| a.date.cmp(&b.date));
-            let mut writer = WriterBuilder::new()
-                .delimiter(mfinance::DELIMITER)
-                .from_writer(
-                    OpenOptions::new()
-                        .write(true)
-                        .truncate(true)
-                        .open(&file)
-                        .map_err(|source| AppError::Io {
-                            source,
-                            context: String::from("Failed to open file when saving sorted csv"),
-                        })?,
-                );
-
-            for entry in entries {
-                writer.serialize(entry)?;
+            let names: Vec<_> = files
+                .iter()
+                .map(|file| file.file_name().unwrap_or_default().to_string_lossy())
+                .collect();
+            if let Some(file) = &file
+                && !names.iter().any(|name| name == file)
+            {
+                return Err(AppError::Io {
+                    source: std::io::Error::new(std::io::ErrorKind::NotFound, "No such file"),
+                    context: format!(
+                        "No file named {file} in directory: {}, available files: {}",
+                        path.display(),
+                        names.join(", ")
+                    ),
+                }
+                .into());
             }
-            writer.flush().map_err(|source| AppError::Io {
+            // An exact name is always the best match
+            let select = file.or(select);
+            tui::run_tui(files, config, select.as_deref(), clock)?;
+        }
+        Commands::Sort {
+            separator,
+            separator_output,
+            output,
+            stamp,
+            two_digit_year_pivot,
+            blank_as_zero,
+            file,
+        } => {
+            let output = output.unwrap_or_else(|| file.clone());
+            let _locks = lock_files(&[&file, &output])?;
+            backup(&config.backup, &output)?;
+            let amount_format = amount_format_of_file_with_delimiter(&file, separator)?;
+            let options = ReadOptions {
+                two_digit_year_pivot,
+                blank_amount_as_zero: blank_as_zero,
+            };
+            let mut entries = entries_from_file_with_options(&file, separator, options)?;
+            profiler.step("read");
+            entries.sort_by_key(|entry| entry.date);
+            profiler.step("sort");
+            let stamp = stamp.then(|| stamp_comment(clock.today()));
+            write_entries_with_delimiter(
+                &output,
+                &entries,
+                amount_format,
+                separator_output.unwrap_or(DELIMITER),
+                stamp.as_deref(),
+            )?;
+            profiler.step("write");
+        }
+        Commands::Migrate { to, file } => {
+            let _lock = lock_file(&file)?;
+            backup(&config.backup, &file)?;
+            let migrated = migrate_entries(&file, to)?;
+            println!("Migrated: {migrated}");
+        }
+        Commands::Checksum { ordered, file } => {
+            let entries = entries_from_file(&file)?;
+            println!("{}", checksum(&entries, ordered));
+        }
+        Commands::Archive { before, into, file } => {
+            let before: NaiveDate = before.parse().map_err(|source| AppError::DateParse {
                 source,
-                context: String::from("Failed to flush the sorted csv writer buffer"),
+                input: before.clone(),
             })?;
+            let _locks = lock_files(&[&file, &into])?;
+            backup(&config.backup, &file)?;
+            backup(&config.backup, &into)?;
+            let info = archive_entries(&file, &into, before)?;
+            println!("Archived: {}", info.moved);
+            println!("Remaining: {}", info.remaining);
+        }
+        Commands::Convert {
+            rate,
+            output,
+            stamp,
+            file,
+        } => {
+            // The output may be the CSV file itself
+            let _lock = lock_file(&output)?;
+            backup(&config.backup, &output)?;
+            let amount_format = amount_format_of_file(&file)?;
+            let mut entries = entries_from_file(&file)?;
+            let convert = |amount: Decimal| {
+                amount
+                    .checked_mul(rate)
+                    .map(|converted| converted.round_dp(2))
+                    .ok_or(AppError::ConversionOverflow { amount, rate })
+            };
+            for entry in &mut entries {
+                entry.amount = convert(entry.amount)?;
+                entry.full_amount = entry.full_amount.map(convert).transpose()?;
+            }
+            let stamp = stamp.then(|| stamp_comment(clock.today()));
+            write_entries_with_delimiter(
+                &output,
+                &entries,
+                amount_format,
+                DELIMITER,
+                stamp.as_deref(),
+            )?;
+            let total: Decimal = entries.iter().map(|entry| entry.amount).sum();
+            println!("Total: {}", total.format(&format_options));
+        }
+        Commands::Dates { format, file } => {
+            let entries = entries_from_file(&file)?;
+            let dates = group_by_period(&entries, Period::Day);
+            match format {
+                OutputFormat::Text => {
+                    let count_width = dates
+                        .iter()
+                        .map(|date| date.count.to_string().len())
+                        .max()
+                        .unwrap_or(0);
+                    for date in dates {
+                        println!("{}: {:>count_width$}", date.period, date.count);
+                    }
+                }
+                OutputFormat::Json => {
+                    let dates: Vec<_> = dates
+                        .into_iter()
+                        .map(|date| serde_json::json!({ "date": date.period, "count": date.count }))
+                        .collect();
+                    println!("{}", serde_json::to_string_pretty(&dates)?);
+                }
+            }
+        }
+        Commands::Doctor { path } => {
+            let mut invalid_rows = 0;
+            for (file, errors) in mfinance::validate_directory(&path)? {
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                if errors.is_empty() {
+                    println!("{name}: OK");
+                }
+                for error in &errors {
+                    match error.line {
+                        Some(line) => println!("{name}:{line}: {}", error.reason),
+                        None => println!("{name}: {}", error.reason),
+                    }
+                }
+                invalid_rows += errors.len();
+            }
+            if invalid_rows > 0 {
+                return Err(AppError::InvalidRows(invalid_rows).into());
+            }
+        }
+        Commands::Return {
+            final_balance,
+            file,
+        } => {
+            let entries = entries_from_file(&file)?;
+            let info = ReturnInfo::new(&entries, final_balance);
+            print!("{}", info.display(format_options));
+        }
+        Commands::Export { format, file } => {
+            let report = generate_report_for_all(&file)?;
+            match format {
+                ExportFormat::Ledger => {
+                    // The account is named after the file, e.g. `Assets:expenses`
+                    let account = file
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy())
+                        .unwrap_or_default();
+                    print!("{}", report.to_ledger(&account));
+                }
+            }
+        }
+        Commands::Config { action, .. } => {
+            let describe = |path: Option<PathBuf>| {
+                path.map_or_else(|| "none".to_string(), |p| p.display().to_string())
+            };
+            let (show_paths, show_settings) = match action {
+                None => (true, true),
+                Some(ConfigAction::Path { .. }) => (true, false),
+                Some(ConfigAction::Show { .. }) => (false, true),
+                Some(ConfigAction::Sample { .. }) => {
+                    println!("{}", Decimal::new(123456789, 2).format(&format_options));
+                    return Ok(());
+                }
+            };
+            if show_paths {
+                println!("Global config: {}", describe(global_config));
+                println!("Local config: {}", describe(data_config));
+            }
+            if show_paths && show_settings {
+                println!();
+            }
+            if show_settings {
+                print!("{config}");
+            }
+        }
+        Commands::Completions { shell } => {
+            clap_complete::generate(
+                shell,
+                &mut Cli::command(),
+                "mfinance",
+                &mut std::io::stdout(),
+            );
+        }
+        Commands::Init { force, file } => {
+            if file.exists() && !force {
+                return Err(AppError::Io {
+                    source: std::io::Error::from(std::io::ErrorKind::AlreadyExists),
+                    context: format!(
+                        "File already exists, use --force to overwrite it: {}",
+                        file.display()
+                    ),
+                }
+                .into());
+            }
+            let entries = sample_entries();
+            let _lock = lock_file(&file)?;
+            backup(&config.backup, &file)?;
+            write_entries(&file, &entries, AmountFormat::default())?;
+            println!(
+                "Created {} with {} example entries",
+                file.display(),
+                entries.len()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Locks each of `paths` once, e.g. the input and the output of a command,
+/// which may be the same file.
+fn lock_files(paths: &[&Path]) -> Result<Vec<FileLock>, AppError> {
+    let mut locked: Vec<PathBuf> = Vec::new();
+    let mut locks = Vec::new();
+    for path in paths {
+        // A path that doesn't exist yet can't be another spelling of one that does
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if !locked.contains(&canonical) {
+            locks.push(lock_file(path)?);
+            locked.push(canonical);
         }
     }
+    Ok(locks)
+}
 
+/// Backs up a file that is about to change if backups are enabled.
+fn backup(config: &config::BackupConfig, file: &Path) -> Result<(), AppError> {
+    if config.enabled {
+        backup_file(file, config.overwrite)?;
+    }
     Ok(())
 }
 
-fn load_config(cli: &Cli) -> Result<config::Config, ::config::ConfigError> {
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        [byte] => Ok(*byte),
+        _ => Err(format!(
+            "delimiter must be a single ASCII character, got '{s}'"
+        )),
+    }
+}
+
+/// Prints the total of each CSV file in `dir` and their combined total. Totals
+/// are colored by their sign when printing to a terminal.
+fn print_net_by_file(
+    dir: &Path,
+    sort: bool,
+    format_options: &FormatOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut totals = mfinance::summarize_directory(dir)?;
+    if totals.is_empty() {
+        return Err(AppError::Io {
+            source: std::io::Error::new(std::io::ErrorKind::NotFound, "No CSV files found"),
+            context: format!("No CSV files found in directory: {}", dir.display()),
+        }
+        .into());
+    }
+    if sort {
+        // Stable sort keeps equal totals in the order of their names
+        totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    }
+    let combined: Decimal = totals.iter().map(|(_, total)| total).sum();
+    let mut rows: Vec<(String, Decimal)> = totals
+        .into_iter()
+        .map(|(path, total)| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            (format!("{name}:"), total)
+        })
+        .collect();
+    rows.push((String::from("Total amount:"), combined));
+
+    let is_terminal = std::io::stdout().is_terminal();
+    let prefix_width = rows
+        .iter()
+        .map(|(prefix, _)| display_width(prefix))
+        .max()
+        .unwrap_or(0);
+    let amount_width = rows
+        .iter()
+        .map(|(_, total)| display_width(&total.format(format_options)))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    for (prefix, total) in rows {
+        let prefix = pad_left(&prefix, prefix_width);
+        let amount = pad_left(&total.format(format_options), amount_width);
+        // Red for a negative, green for a positive net position
+        let color = if total.is_sign_negative() { 31 } else { 32 };
+        if is_terminal && !total.is_zero() {
+            println!("{prefix}\x1b[{color}m{amount}\x1b[0m");
+        } else {
+            println!("{prefix}{amount}");
+        }
+    }
+    Ok(())
+}
+
+/// The CSV file a command reads, with the delimiter it reads it with.
+fn csv_file(command: &Commands) -> Option<(PathBuf, u8)> {
+    match command {
+        Commands::Sort {
+            separator, file, ..
+        } => Some((file.clone(), *separator)),
+        Commands::NewEntry { file, .. }
+        | Commands::Recur { file, .. }
+        | Commands::Report { file, .. }
+        | Commands::Archive { file, .. }
+        | Commands::Dates { file, .. }
+        | Commands::Convert { file, .. }
+        | Commands::Migrate { file, .. }
+        | Commands::Checksum { file, .. }
+        | Commands::Return { file, .. }
+        | Commands::Export { file, .. } => Some((file.clone(), DELIMITER)),
+        Commands::Tui { .. }
+        | Commands::Doctor { .. }
+        | Commands::Config { .. }
+        | Commands::Init { .. }
+        | Commands::Completions { .. } => None,
+    }
+}
+
+/// An `--amount` value, or `-` for an amount read from stdin.
+#[derive(Clone, Copy)]
+enum AmountArg {
+    Value(Decimal),
+    Stdin,
+}
+
+fn parse_amount_arg(input: &str) -> Result<AmountArg, AppError> {
+    if input == "-" {
+        Ok(AmountArg::Stdin)
+    } else {
+        parse_amount(input).map(AmountArg::Value)
+    }
+}
+
+/// Reads an amount from the first line of stdin, e.g. piped from a script.
+fn read_stdin_amount() -> Result<Decimal, AppError> {
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|source| AppError::Io {
+            source,
+            context: String::from("Failed to read the amount from stdin"),
+        })?;
+    let line = line.trim();
+    parse_amount(line).map_err(|_| AppError::AmountParse(format!("{line:?} read from stdin")))
+}
+
+/// Parses an IANA timezone name like `America/New_York` or `UTC`.
+fn parse_timezone(s: &str) -> Result<Tz, String> {
+    s.parse().map_err(|_| {
+        format!("unknown timezone '{s}', expected an IANA name like America/New_York or UTC")
+    })
+}
+
+/// Finds the `mfinance.toml` next to the data the command works on.
+fn data_config_path(cli: &Cli) -> Option<PathBuf> {
     let data_path = match &cli.command {
-        Commands::Tui { path } => Some(path),
+        Commands::Tui { path, .. } => Some(path),
+        Commands::Doctor { path } => Some(path),
         Commands::NewEntry { file, .. } => Some(file),
+        Commands::Recur { file, .. } => Some(file),
         Commands::Report { file, .. } => Some(file),
-        Commands::Sort { file } => Some(file),
+        Commands::Sort { file, .. } => Some(file),
+        Commands::Archive { file, .. } => Some(file),
+        Commands::Dates { file, .. } => Some(file),
+        Commands::Convert { file, .. } => Some(file),
+        Commands::Migrate { file, .. } => Some(file),
+        Commands::Checksum { file, .. } => Some(file),
+        Commands::Return { file, .. } => Some(file),
+        Commands::Export { file, .. } => Some(file),
+        Commands::Config {
+            action:
+                Some(
+                    ConfigAction::Show { path }
+                    | ConfigAction::Path { path }
+                    | ConfigAction::Sample { path },
+                ),
+            ..
+        } => path.as_ref(),
+        Commands::Config { path, .. } => path.as_ref(),
+        Commands::Init { file, .. } => Some(file),
+        Commands::Completions { .. } => None,
     };
     let data_dir = data_path.and_then(|p| {
         if p.exists() {
@@ -152,17 +1131,46 @@ fn load_config(cli: &Cli) -> Result<config::Config, ::config::ConfigError> {
             }
         }
     });
-    let data_config = data_dir
+    data_dir
         .map(|d| d.join("mfinance.toml"))
-        .filter(|p| p.exists());
+        .filter(|p| p.exists())
+}
+
+/// The locale numbers are formatted in, following the POSIX precedence of
+/// `LC_ALL` over `LC_NUMERIC` over `LANG`.
+fn numeric_locale() -> Option<String> {
+    ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
 
+fn load_config(
+    global_config: Option<&Path>,
+    data_config: Option<&Path>,
+    locale_separators: Option<(char, char)>,
+    precedence: ConfigPrecedence,
+) -> Result<config::Config, ::config::ConfigError> {
     let mut settings = ::config::Config::builder();
 
-    if let Some(path) = global_config_path() {
-        settings = settings.add_source(::config::File::from(path.as_ref()).required(false));
+    if let Some((thousands_separator, decimal_separator)) = locale_separators {
+        settings = settings
+            .set_default(
+                "formatting.thousands_separator",
+                thousands_separator.to_string(),
+            )?
+            .set_default(
+                "formatting.decimal_separator",
+                decimal_separator.to_string(),
+            )?;
     }
 
-    if let Some(path) = data_config.as_deref() {
+    // Later sources override earlier ones
+    let sources = match precedence {
+        ConfigPrecedence::Global => [data_config, global_config],
+        ConfigPrecedence::Project => [global_config, data_config],
+    };
+    for path in sources.into_iter().flatten() {
         settings = settings.add_source(::config::File::from(path).required(false));
     }
 
@@ -170,6 +1178,25 @@ fn load_config(cli: &Cli) -> Result<config::Config, ::config::ConfigError> {
     settings.try_deserialize::<config::Config>()
 }
 
+/// Uses the date from `MFINANCE_TODAY` as today when set, e.g. for
+/// reproducible output in tests. Otherwise today is the date in the `tz`
+/// timezone, or the local date of the system.
+fn clock(tz: Option<Tz>) -> Result<Box<dyn Clock>, AppError> {
+    match std::env::var("MFINANCE_TODAY") {
+        Ok(today) => {
+            let today = today.parse().map_err(|source| AppError::DateParse {
+                source,
+                input: today.clone(),
+            })?;
+            Ok(Box::new(FixedClock(today)))
+        }
+        Err(_) => match tz {
+            Some(tz) => Ok(Box::new(TimezoneClock(tz))),
+            None => Ok(Box::new(SystemClock)),
+        },
+    }
+}
+
 fn global_config_path() -> Option<PathBuf> {
     let config_file_name = "config.toml";
     if let Some(config_dir) = std::env::var_os("MFINANCE_CONFIG_DIR") {
@@ -16,16 +16,27 @@ pub struct FormatOptions {
     pub thousands_separator: char,
     pub decimal_separator: char,
     pub currency: CurrencyPosition,
+    /// Left-pads the integer part with zeros to this many digits, e.g. `007 999.99`.
+    pub pad_integer_digits: Option<usize>,
 }
 
 impl NumberFormatter for Decimal {
     fn format(&self, options: &FormatOptions) -> String {
         let precision = 2;
-        let decimal = self.round_dp(precision as u32);
-        let decimal_string =
+        let mut decimal = self.round_dp(precision as u32);
+        if decimal.is_zero() {
+            // Rounding a tiny negative value yields a negative zero, render it as `0.00`.
+            decimal.set_sign_positive(true);
+        }
+        let mut decimal_string =
             format!("{decimal:.precision$}").replace(".", &String::from(options.decimal_separator));
 
         let sign_offset = usize::from(decimal.is_sign_negative());
+        if let Some(width) = options.pad_integer_digits {
+            let integer_digits = decimal_string.len() - 1 - precision - sign_offset;
+            let zeros = "0".repeat(width.saturating_sub(integer_digits));
+            decimal_string.insert_str(sign_offset, &zeros);
+        }
         let len_till_dot = decimal_string.len() - 1 - precision;
         let mut group_separator_index = (len_till_dot - sign_offset) % 3 + sign_offset;
         if group_separator_index == sign_offset {
@@ -59,6 +70,7 @@ mod tests {
                 thousands_separator: '\u{a0}', // Non-breaking space
                 decimal_separator: '.',
                 currency: CurrencyPosition::None,
+                pad_integer_digits: None,
             }
         }
     }
@@ -99,11 +111,53 @@ mod tests {
         insta::assert_snapshot!(Decimal::from(1000).format(&options), @"1 000,00");
     }
 
+    #[test]
+    fn format_with_padded_integer_digits() {
+        let options = FormatOptions {
+            pad_integer_digits: Some(6),
+            ..FormatOptions::default()
+        };
+        insta::assert_snapshot!(Decimal::from_f32(7999.99).unwrap().format(&options), @"007 999.99");
+    }
+
+    #[test]
+    fn format_negative_with_padded_integer_digits() {
+        let options = FormatOptions {
+            pad_integer_digits: Some(6),
+            ..FormatOptions::default()
+        };
+        insta::assert_snapshot!(Decimal::from_f32(-12.5).unwrap().format(&options), @"-000 012.50");
+    }
+
+    #[test]
+    fn format_wider_than_padded_integer_digits() {
+        let options = FormatOptions {
+            pad_integer_digits: Some(2),
+            ..FormatOptions::default()
+        };
+        insta::assert_snapshot!(Decimal::from(1000).format(&options), @"1 000.00");
+    }
+
     #[test]
     fn format_fractions_negative() {
         insta::assert_snapshot!(Decimal::from_f32(-0.006).unwrap().format(&FormatOptions::default()), @r"-0.01");
     }
 
+    #[test]
+    fn format_negative_zero_after_rounding() {
+        insta::assert_snapshot!(Decimal::from_f32(-0.004).unwrap().format(&FormatOptions::default()), @r"0.00");
+    }
+
+    #[test]
+    fn format_tiny_negative_fraction() {
+        insta::assert_snapshot!(Decimal::from_f32(-0.001).unwrap().format(&FormatOptions::default()), @r"0.00");
+    }
+
+    #[test]
+    fn format_zero_with_scale() {
+        insta::assert_snapshot!(Decimal::new(0, 3).format(&FormatOptions::default()), @r"0.00");
+    }
+
     #[test]
     fn format_fractions() {
         let options = FormatOptions::default();
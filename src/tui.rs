@@ -1,31 +1,35 @@
-use crate::add_entry;
+use crate::clock::Clock;
 use crate::{
-    DELIMITER, Entry,
-    config::Config,
-    entries_from_file,
+    AppError, Entry, amount_format_of_file, append_entries, backup_file,
+    config::{AccountKind, Config, KeysConfig, TuiView},
+    display_width, entries_from_file, entries_from_file_retrying, evaluate_amount, lock_file,
     number_formatter::{FormatOptions, NumberFormatter},
+    pad_left, parse_amount, write_entries,
 };
 use chrono::Datelike;
 use chrono::NaiveDate;
-use csv::WriterBuilder;
 use ratatui::crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, layout::Position as CursorPosition, prelude::*, widgets::*};
 use rust_decimal::Decimal;
 use std::{
-    collections::BTreeMap,
-    fs::OpenOptions,
+    collections::{BTreeMap, HashSet},
     path::{Path, PathBuf},
-    str::FromStr,
 };
 use tui_input::{Input, backend::crossterm::EventHandler};
 
 const FOCUSED_SELECTION_BG_COLOR: Color = Color::from_u32(0x001a1e24);
 const UNFOCUSED_SELECTION_BG_COLOR: Color = Color::from_u32(0x00232730);
+const STRIPE_BG_COLOR: Color = Color::from_u32(0x00161616);
 const SELECTION_INDICATOR_COLOR: Color = Color::Green;
+const CURRENT_MONTH_COLOR: Color = Color::Cyan;
+const TODAY_COLOR: Color = Color::Yellow;
 const FIXED_PADDING_WIDTH: usize = 2;
 
 /// Core TUI loop that works with any backend and event source
@@ -34,6 +38,8 @@ const FIXED_PADDING_WIDTH: usize = 2;
 pub fn run_tui_loop<B, E>(
     files: Vec<PathBuf>,
     config: Config,
+    select: Option<&str>,
+    clock: Box<dyn Clock>,
     terminal: &mut Terminal<B>,
     events: E,
 ) -> Result<(), Box<dyn std::error::Error>>
@@ -46,7 +52,8 @@ where
         .into_iter()
         .map(|path| File::new(path))
         .collect::<Result<Vec<_>, _>>()?;
-    let mut app = App::new(files, config);
+    let normal_bindings = normal_bindings(&config.keys);
+    let mut app = App::new(files, config, select, clock);
 
     // Draw initial state
     terminal.draw(|f| ui(f, &mut app))?;
@@ -57,27 +64,47 @@ where
             && key.kind == KeyEventKind::Press
         {
             let bindings: &[KeyBinding] = match app.popup.mode {
-                PopupMode::None => NORMAL_BINDINGS,
-                _ => POPUP_BINDINGS,
+                PopupMode::None => &normal_bindings,
+                PopupMode::Info | PopupMode::Error => INFO_BINDINGS,
+                PopupMode::AddEntry | PopupMode::EditEntry => POPUP_BINDINGS,
+                PopupMode::Goto => GOTO_BINDINGS,
             };
-            if let Some(binding) = bindings.iter().find(|b| b.code == key.code) {
+            let is_paste =
+                key.code == KeyCode::Char('v') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if is_paste {
+                // Without clipboard access there's simply nothing to paste
+                if app.is_editing_popup()
+                    && let Some(text) = clipboard_text()
+                {
+                    app.paste_into_popup(&text);
+                }
+            } else if let Some(binding) = bindings.iter().find(|b| b.code == key.code) {
                 match binding.action {
                     KeyAction::Quit => break,
                     KeyAction::Next => app.next(),
                     KeyAction::Previous => app.previous(),
                     KeyAction::CycleFocus => app.cycle_focus(),
                     KeyAction::ToggleViewMode => app.toggle_view_mode(),
+                    KeyAction::ToggleAbsoluteAmounts => app.toggle_absolute_amounts(),
                     KeyAction::NewEntry => app.open_add_entry_popup(),
                     KeyAction::EditEntry => app.open_edit_entry_popup(),
+                    KeyAction::ShowInfo => app.open_info_popup(),
+                    KeyAction::GotoDate => app.open_goto_popup(),
+                    KeyAction::ToggleYear => app.toggle_year(),
                     KeyAction::ClosePopup => app.close_popup(),
                     KeyAction::CyclePopupFocus => app.cycle_popup_focus(),
+                    KeyAction::SavePopup if app.popup.mode == PopupMode::Goto => {
+                        app.handle_goto_popup()
+                    }
                     KeyAction::SavePopup => app.handle_saving_popup_entry(),
                 }
-            } else if app.popup.mode != PopupMode::None
+            } else if app.is_editing_popup()
                 && matches!(key.code, KeyCode::Char(_) | KeyCode::Backspace)
             {
                 app.handle_popup_input(key);
             }
+        } else if let Event::Paste(text) = &event {
+            app.paste_into_popup(text);
         }
 
         // Redraw after each event
@@ -98,81 +125,142 @@ enum KeyAction {
     Previous,
     CycleFocus,
     ToggleViewMode,
+    ToggleAbsoluteAmounts,
     NewEntry,
     EditEntry,
+    ShowInfo,
+    GotoDate,
+    ToggleYear,
     ClosePopup,
     CyclePopupFocus,
     SavePopup,
 }
 
-const NORMAL_BINDINGS: &[KeyBinding] = &[
+fn normal_bindings(keys: &KeysConfig) -> Vec<KeyBinding> {
+    let mut bindings = vec![
+        KeyBinding {
+            code: KeyCode::Char(keys.quit),
+            action: KeyAction::Quit,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.add),
+            action: KeyAction::NewEntry,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.edit),
+            action: KeyAction::EditEntry,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.info),
+            action: KeyAction::ShowInfo,
+        },
+        KeyBinding {
+            code: KeyCode::Down,
+            action: KeyAction::Next,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.next),
+            action: KeyAction::Next,
+        },
+        KeyBinding {
+            code: KeyCode::Up,
+            action: KeyAction::Previous,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.prev),
+            action: KeyAction::Previous,
+        },
+        KeyBinding {
+            code: KeyCode::Tab,
+            action: KeyAction::CycleFocus,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.view),
+            action: KeyAction::ToggleViewMode,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.absolute),
+            action: KeyAction::ToggleAbsoluteAmounts,
+        },
+        KeyBinding {
+            code: KeyCode::Char(keys.goto),
+            action: KeyAction::GotoDate,
+        },
+        KeyBinding {
+            code: KeyCode::Enter,
+            action: KeyAction::ToggleYear,
+        },
+    ];
+    if let Some(key) = keys.focus_next {
+        bindings.push(KeyBinding {
+            code: KeyCode::Char(key),
+            action: KeyAction::CycleFocus,
+        });
+    }
+    bindings
+}
+
+const POPUP_BINDINGS: &[KeyBinding] = &[
     KeyBinding {
         code: KeyCode::Char('q'),
-        action: KeyAction::Quit,
-    },
-    KeyBinding {
-        code: KeyCode::Char('n'),
-        action: KeyAction::NewEntry,
-    },
-    KeyBinding {
-        code: KeyCode::Char('e'),
-        action: KeyAction::EditEntry,
-    },
-    KeyBinding {
-        code: KeyCode::Down,
-        action: KeyAction::Next,
-    },
-    KeyBinding {
-        code: KeyCode::Char('j'),
-        action: KeyAction::Next,
+        action: KeyAction::ClosePopup,
     },
     KeyBinding {
-        code: KeyCode::Up,
-        action: KeyAction::Previous,
+        code: KeyCode::Tab,
+        action: KeyAction::CyclePopupFocus,
     },
     KeyBinding {
-        code: KeyCode::Char('k'),
-        action: KeyAction::Previous,
+        code: KeyCode::Enter,
+        action: KeyAction::SavePopup,
     },
+];
+
+/// Like [`POPUP_BINDINGS`], but without Tab, since the popup has only the date field.
+const GOTO_BINDINGS: &[KeyBinding] = &[
     KeyBinding {
-        code: KeyCode::Tab,
-        action: KeyAction::CycleFocus,
+        code: KeyCode::Char('q'),
+        action: KeyAction::ClosePopup,
     },
     KeyBinding {
-        code: KeyCode::Char('v'),
-        action: KeyAction::ToggleViewMode,
+        code: KeyCode::Enter,
+        action: KeyAction::SavePopup,
     },
 ];
 
-const POPUP_BINDINGS: &[KeyBinding] = &[
+const INFO_BINDINGS: &[KeyBinding] = &[
     KeyBinding {
         code: KeyCode::Char('q'),
         action: KeyAction::ClosePopup,
     },
-    KeyBinding {
-        code: KeyCode::Tab,
-        action: KeyAction::CyclePopupFocus,
-    },
     KeyBinding {
         code: KeyCode::Enter,
-        action: KeyAction::SavePopup,
+        action: KeyAction::ClosePopup,
     },
 ];
 
-pub fn run_tui(files: Vec<PathBuf>, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run_tui(
+    files: Vec<PathBuf>,
+    config: Config,
+    select: Option<&str>,
+    clock: Box<dyn Clock>,
+) -> Result<(), Box<dyn std::error::Error>> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Event iterator that reads from stdin until quit
     let events = std::iter::from_fn(|| event::read().ok());
 
-    let res = run_tui_loop(files, config, &mut terminal, events);
+    let res = run_tui_loop(files, config, select, clock, &mut terminal, events);
 
     disable_raw_mode()?;
-    execute!(std::io::stdout(), LeaveAlternateScreen)?;
+    execute!(
+        std::io::stdout(),
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    )?;
     res
 }
 
@@ -188,6 +276,11 @@ enum PopupMode {
     None,
     AddEntry,
     EditEntry,
+    Info,
+    /// Shows why the selected file failed to load.
+    Error,
+    /// Asks for a date to jump to.
+    Goto,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -205,11 +298,18 @@ enum PopupFocus {
 struct App {
     files: Vec<File>,
     config: Config,
+    clock: Box<dyn Clock>,
     report: ReportViewModel,
     selection: Selection,
     focus: Focus,
     view_mode: ViewMode,
     popup: Popup,
+    /// Number of entries added through the popup since the TUI started.
+    added_entries: usize,
+    /// Shows entry amounts without their sign, totals stay signed.
+    absolute_amounts: bool,
+    /// Years showing their entries inline in the tree view.
+    expanded: HashSet<String>,
 }
 
 struct Popup {
@@ -237,6 +337,9 @@ struct Selection {
     file: usize,
     year: usize,
     entry: usize,
+    /// In the tree view, whether an entry row of the year is selected rather
+    /// than the year itself.
+    entry_row: bool,
 }
 
 #[derive(Default)]
@@ -263,13 +366,11 @@ impl DebitCreditAmount {
     }
 
     fn credit_width(&self) -> usize {
-        Span::raw(self.credit.as_str()).width()
+        display_width(&self.credit)
     }
 
     fn display(&self, credit_width: usize) -> String {
-        let actual_width = Span::raw(self.credit.as_str()).width();
-        let pad = " ".repeat(credit_width.saturating_sub(actual_width));
-        format!("{} | {}{}", self.debit, pad, self.credit)
+        format!("{} | {}", self.debit, pad_left(&self.credit, credit_width))
     }
 }
 
@@ -294,17 +395,13 @@ struct YearReportViewModel {
 }
 
 impl ReportViewModel {
-    fn new(
-        file: &File,
-        format_options: &FormatOptions,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let entries = entries_from_file(&file.path)?;
+    fn new(file: &File, format_options: &FormatOptions) -> Result<Self, AppError> {
+        let entries = entries_from_file_retrying(&file.path)?;
         let total: Decimal = entries.iter().map(|entry| entry.amount).sum();
         let (debit, credit) = split_debit_credit(&entries);
         let mut years_map: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
         for entry in entries {
-            let date: NaiveDate = entry.date.parse()?;
-            let year = date.year().to_string();
+            let year = entry.date.year().to_string();
             years_map.entry(year).or_default().push(entry);
         }
         let year_reports: Vec<YearReportViewModel> = years_map
@@ -363,15 +460,26 @@ impl File {
 }
 
 impl App {
-    fn new(files: Vec<File>, config: Config) -> Self {
+    fn new(files: Vec<File>, config: Config, select: Option<&str>, clock: Box<dyn Clock>) -> Self {
+        let file = select
+            .and_then(|query| best_fuzzy_match(files.iter().map(|file| file.name.as_str()), query))
+            .unwrap_or(0);
+        let absolute_amounts = config.tui.absolute_amounts;
         let mut app = Self {
             files,
             config,
+            clock,
             focus: Focus::Files,
             view_mode: ViewMode::Total,
             report: ReportViewModel::default(),
-            selection: Selection::default(),
+            selection: Selection {
+                file,
+                ..Selection::default()
+            },
             popup: Popup::new(),
+            added_entries: 0,
+            absolute_amounts,
+            expanded: HashSet::new(),
         };
         app.reload_file();
         app.select_last_year();
@@ -379,9 +487,14 @@ impl App {
         app
     }
 
+    fn is_tree_view(&self) -> bool {
+        self.config.tui.view == TuiView::Tree
+    }
+
     fn cycle_focus(&mut self) {
         self.focus = match self.focus {
             Focus::Files => Focus::Years,
+            Focus::Years if self.is_tree_view() => Focus::Files,
             Focus::Years => Focus::YearDetails,
             Focus::YearDetails => Focus::Files,
         };
@@ -394,14 +507,21 @@ impl App {
         };
     }
 
+    fn toggle_absolute_amounts(&mut self) {
+        self.absolute_amounts = !self.absolute_amounts;
+    }
+
     fn next(&mut self) {
         match self.focus {
             Focus::Files => {
                 self.selection.file = next_index_cycled(self.selection.file, self.files.len());
                 self.reload_file();
+                self.expanded.clear();
+                self.selection.entry_row = false;
                 self.select_last_year();
                 self.select_last_entry();
             }
+            Focus::Years if self.is_tree_view() => self.select_tree_row(true),
             Focus::Years => {
                 self.selection.year =
                     next_index_cycled(self.selection.year, self.report.year_reports.len());
@@ -419,9 +539,12 @@ impl App {
             Focus::Files => {
                 self.selection.file = previous_index_cycled(self.selection.file, self.files.len());
                 self.reload_file();
+                self.expanded.clear();
+                self.selection.entry_row = false;
                 self.select_last_year();
                 self.select_last_entry();
             }
+            Focus::Years if self.is_tree_view() => self.select_tree_row(false),
             Focus::Years => {
                 self.selection.year =
                     previous_index_cycled(self.selection.year, self.report.year_reports.len());
@@ -435,12 +558,21 @@ impl App {
     }
 
     fn reload_file(&mut self) {
-        if let Some(path) = self.files.get(self.selection.file) {
-            match ReportViewModel::new(path, &self.config.formatting.format_options()) {
+        if let Some(file) = self.files.get(self.selection.file) {
+            match ReportViewModel::new(file, &self.config.formatting.format_options()) {
                 Ok(report) => {
                     self.report = report;
                 }
-                Err(e) => eprintln!("Error loading file: {e}"),
+                Err(e) => {
+                    // Don't leave the previous file's entries on screen
+                    self.report = ReportViewModel {
+                        title: file.name.clone(),
+                        ..ReportViewModel::default()
+                    };
+                    self.popup = Popup::new();
+                    self.popup.mode = PopupMode::Error;
+                    self.popup.error_message = Some(e.to_string());
+                }
             }
         }
     }
@@ -458,6 +590,58 @@ impl App {
             .unwrap_or(0);
     }
 
+    /// Rows of the years pane in the tree view: every year, followed by its
+    /// entries if it's expanded.
+    fn tree_rows(&self) -> Vec<(usize, Option<usize>)> {
+        let mut rows = Vec::new();
+        for (year_index, year) in self.report.year_reports.iter().enumerate() {
+            rows.push((year_index, None));
+            if self.expanded.contains(&year.title) {
+                rows.extend((0..year.entries.len()).map(|entry| (year_index, Some(entry))));
+            }
+        }
+        rows
+    }
+
+    fn selected_tree_row(&self, rows: &[(usize, Option<usize>)]) -> usize {
+        let entry = self.selection.entry_row.then_some(self.selection.entry);
+        rows.iter()
+            .position(|&row| row == (self.selection.year, entry))
+            .unwrap_or(0)
+    }
+
+    fn select_tree_row(&mut self, forward: bool) {
+        let rows = self.tree_rows();
+        let current = self.selected_tree_row(&rows);
+        let index = if forward {
+            next_index_cycled(current, rows.len())
+        } else {
+            previous_index_cycled(current, rows.len())
+        };
+        if let Some(&(year, entry)) = rows.get(index) {
+            self.selection.year = year;
+            self.selection.entry_row = entry.is_some();
+            match entry {
+                Some(entry) => self.selection.entry = entry,
+                None => self.select_last_entry(),
+            }
+        }
+    }
+
+    /// Expands the selected year in the tree view to show its entries inline,
+    /// or collapses the year of the selected row.
+    fn toggle_year(&mut self) {
+        if !self.is_tree_view() || self.focus != Focus::Years {
+            return;
+        }
+        if let Some(year) = self.report.year_reports.get(self.selection.year)
+            && !self.expanded.remove(&year.title)
+        {
+            self.expanded.insert(year.title.clone());
+        }
+        self.selection.entry_row = false;
+    }
+
     fn year_entries_count(&self) -> usize {
         self.report
             .year_reports
@@ -470,14 +654,36 @@ impl App {
         self.popup.mode = PopupMode::AddEntry;
         self.popup.focus = PopupFocus::Amount;
         // Set current date as default
-        self.popup.date_input = Input::new(chrono::Local::now().date_naive().to_string());
-        self.popup.amount_input = Input::default();
+        self.popup.date_input = Input::new(self.clock.today().to_string());
+        // Pre-fill the sign for files configured as expense accounts
+        let file = &self.files[self.selection.file];
+        self.popup.amount_input = match self.config.account_kind(&file.name) {
+            Some(AccountKind::Expense) => Input::new(String::from("-")),
+            Some(AccountKind::Income) | None => Input::default(),
+        };
         self.popup.error_message = None;
     }
 
+    /// The amount in the popup, which may be an arithmetic expression. When
+    /// adding to an expense account, a leading `-` like the prefilled one
+    /// negates the whole expression, so `-12 + 3` is `-15`. An edited amount
+    /// starts with the stored value instead, so there `-12 + 3` is `-9`.
+    fn popup_amount(&self) -> Result<Decimal, AppError> {
+        let input = self.popup.amount_input.value();
+        let file = &self.files[self.selection.file];
+        let is_adding_expense = self.popup.mode == PopupMode::AddEntry
+            && self.config.account_kind(&file.name) == Some(AccountKind::Expense);
+        match input.strip_prefix('-') {
+            Some(expression) if is_adding_expense => {
+                evaluate_amount(expression).map(|amount| -amount)
+            }
+            _ => evaluate_amount(input),
+        }
+    }
+
     fn open_edit_entry_popup(&mut self) {
         if let Some(selected_entry) = self.get_selected_entry() {
-            let date_input = selected_entry.date.clone();
+            let date_input = selected_entry.date.to_string();
             let amount_input = selected_entry.amount.to_string();
 
             self.popup.mode = PopupMode::EditEntry;
@@ -488,11 +694,76 @@ impl App {
         }
     }
 
+    fn open_info_popup(&mut self) {
+        if self.get_selected_entry().is_some() {
+            self.popup.mode = PopupMode::Info;
+        }
+    }
+
+    fn open_goto_popup(&mut self) {
+        self.popup.mode = PopupMode::Goto;
+        self.popup.focus = PopupFocus::Date;
+        self.popup.date_input = Input::default();
+        self.popup.error_message = None;
+    }
+
+    /// Selects the first entry on or after the popup date, given as
+    /// `YYYY-MM-DD`, `YYYY-MM` or `YYYY`.
+    fn handle_goto_popup(&mut self) {
+        let value = self.popup.date_input.value();
+        let Some(date) = [
+            value.to_string(),
+            format!("{value}-01"),
+            format!("{value}-01-01"),
+        ]
+        .iter()
+        .find_map(|text| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()) else {
+            self.popup.error_message = Some("Invalid date format. Use YYYY-MM-DD".to_string());
+            return;
+        };
+
+        let closest = self
+            .report
+            .year_reports
+            .iter()
+            .enumerate()
+            .flat_map(|(year, report)| {
+                report
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .map(move |(entry, e)| (e.date, year, entry))
+            })
+            .filter(|(entry_date, _, _)| *entry_date >= date)
+            .min();
+        match closest {
+            Some((_, year, entry)) => {
+                self.selection.year = year;
+                self.selection.entry = entry;
+                if self.is_tree_view() {
+                    let title = self.report.year_reports[year].title.clone();
+                    self.expanded.insert(title);
+                    self.selection.entry_row = true;
+                    self.focus = Focus::Years;
+                } else {
+                    self.focus = Focus::YearDetails;
+                }
+                self.close_popup();
+            }
+            None => {
+                self.popup.error_message = Some(format!("No entries on or after {date}"));
+            }
+        }
+    }
+
     fn close_popup(&mut self) {
         self.popup = Popup::new();
     }
 
     fn get_selected_entry(&self) -> Option<&Entry> {
+        if self.is_tree_view() && !self.selection.entry_row {
+            return None;
+        }
         self.report
             .year_reports
             .get(self.selection.year)?
@@ -507,7 +778,15 @@ impl App {
         };
     }
 
-    fn handle_popup_input(&mut self, key_event: ratatui::crossterm::event::KeyEvent) {
+    /// Whether a popup with input fields is open.
+    fn is_editing_popup(&self) -> bool {
+        matches!(
+            self.popup.mode,
+            PopupMode::AddEntry | PopupMode::EditEntry | PopupMode::Goto
+        )
+    }
+
+    fn handle_popup_input(&mut self, key_event: KeyEvent) {
         // Clear error message when user starts typing
         if matches!(key_event.code, KeyCode::Char(_) | KeyCode::Backspace) {
             self.popup.error_message = None;
@@ -517,8 +796,9 @@ impl App {
             PopupFocus::Date => {
                 self.popup.date_input.handle_event(&Event::Key(key_event));
                 // Ensure date doesn't exceed 10 characters (YYYY-MM-DD format)
-                if self.popup.date_input.value().len() > 10 {
-                    let truncated = self.popup.date_input.value()[..10].to_string();
+                if self.popup.date_input.value().chars().count() > 10 {
+                    let truncated: String =
+                        self.popup.date_input.value().chars().take(10).collect();
                     self.popup.date_input = Input::new(truncated).with_cursor(10);
                 }
             }
@@ -526,11 +806,7 @@ impl App {
                 // For amount field, we need to validate input
                 let key = key_event.code;
                 match key {
-                    KeyCode::Char(c) if c.is_ascii_digit() || c == '.' || c == '-' => {
-                        // Only allow minus at the beginning
-                        if c == '-' && !self.popup.amount_input.value().is_empty() {
-                            return;
-                        }
+                    KeyCode::Char(c) if is_amount_char(c) => {
                         self.popup.amount_input.handle_event(&Event::Key(key_event));
                     }
                     KeyCode::Backspace => {
@@ -542,6 +818,17 @@ impl App {
         }
     }
 
+    /// Inserts pasted text into the focused field as if it was typed, so the
+    /// field keeps only the characters it accepts.
+    fn paste_into_popup(&mut self, text: &str) {
+        if !self.is_editing_popup() {
+            return;
+        }
+        for c in text.trim().chars() {
+            self.handle_popup_input(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
     fn handle_saving_popup_entry(&mut self) {
         // Clear any previous error message
         self.popup.error_message = None;
@@ -555,7 +842,7 @@ impl App {
             }
         };
 
-        let amount = match Decimal::from_str(self.popup.amount_input.value()) {
+        let amount = match self.popup_amount() {
             Ok(amount) => amount,
             Err(_) => {
                 self.popup.error_message =
@@ -564,22 +851,40 @@ impl App {
             }
         };
 
-        let file = &self.files[self.selection.file];
+        // Back up under the lock, so that no other write lands in between
+        let path = self.files[self.selection.file].path.clone();
+        let lock = match lock_file(&path) {
+            Ok(lock) => lock,
+            Err(e) => {
+                self.popup.error_message = Some(format!("Failed to save: {}", e));
+                return;
+            }
+        };
+        if self.config.backup.enabled
+            && let Err(e) = backup_file(&path, self.config.backup.overwrite)
+        {
+            self.popup.error_message = Some(format!("Failed to back up: {}", e));
+            return;
+        }
 
         let result = match self.popup.mode {
-            PopupMode::AddEntry => add_entry(&file.path, date, amount)
-                .map(|_| ())
-                .map_err(|err| err.into()),
-            PopupMode::EditEntry => self.edit_entry_in_file(&file.path, date, amount),
-            PopupMode::None => Ok(()),
+            PopupMode::AddEntry => {
+                let entry = Entry {
+                    date,
+                    amount,
+                    full_amount: None,
+                };
+                append_entries(&path, &[entry])
+                    .map(|_| ())
+                    .map_err(|err| err.into())
+            }
+            PopupMode::EditEntry => self.edit_entry_in_file(&path, date, amount),
+            PopupMode::None | PopupMode::Info | PopupMode::Error | PopupMode::Goto => Ok(()),
         };
+        drop(lock);
 
         match result {
-            Ok(()) => {
-                // Success - refresh the report and close popup
-                self.reload_file();
-                self.close_popup();
-            }
+            Ok(()) => self.finish_saving(),
             Err(e) => {
                 // Error - show error message and keep popup open
                 self.popup.error_message = Some(format!("Failed to save: {}", e));
@@ -587,36 +892,35 @@ impl App {
         }
     }
 
+    /// Closes the popup after a successful save and shows the file again. The
+    /// popup is closed first, so that an error loading the file replaces it.
+    fn finish_saving(&mut self) {
+        if self.popup.mode == PopupMode::AddEntry {
+            self.added_entries += 1;
+        }
+        self.close_popup();
+        self.reload_file();
+    }
+
+    /// Replaces the selected entry in the file, whose lock the caller holds.
     fn edit_entry_in_file(
         &self,
         file_path: &Path,
         date: NaiveDate,
         amount: Decimal,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let amount_format = amount_format_of_file(file_path)?;
         let mut entries = entries_from_file(file_path)?;
 
         // Find and update the entry
         if let Some(selected_entry) = self.get_selected_entry() {
             // Find the entry by matching date and amount (original values)
-            if let Some(entry_to_edit) = entries
-                .iter_mut()
-                .find(|e| e.date == selected_entry.date && e.amount == selected_entry.amount)
-            {
-                entry_to_edit.date = date.to_string();
+            if let Some(entry_to_edit) = entries.iter_mut().find(|e| *e == selected_entry) {
+                entry_to_edit.date = date;
                 entry_to_edit.amount = amount;
 
                 // Rewrite the entire file
-                let mut writer = WriterBuilder::new().delimiter(DELIMITER).from_writer(
-                    OpenOptions::new()
-                        .write(true)
-                        .truncate(true)
-                        .open(file_path)?,
-                );
-
-                for entry in entries {
-                    writer.serialize(entry)?;
-                }
-                writer.flush()?;
+                write_entries(file_path, &entries, amount_format)?;
             }
         }
 
@@ -630,10 +934,49 @@ fn ui(frame: &mut Frame, app: &mut App) {
         .constraints([Constraint::Min(3), Constraint::Length(3)])
         .areas(frame.area());
 
-    let [files_rect, years_rect, entries_rect] = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Ratio(1, 3); 3])
-        .areas(main_rect);
+    let main_rect = if app.config.tui.show_directory {
+        let [title_rect, panes_rect] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(3)])
+            .areas(main_rect);
+        let directory = app.files[app.selection.file]
+            .path
+            .parent()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::raw(" "),
+                Span::raw(directory).style(Modifier::BOLD),
+            ])),
+            title_rect,
+        );
+        panes_rect
+    } else {
+        main_rect
+    };
+
+    let ratios = app.config.layout.ratios();
+    let ratios_sum = ratios.iter().sum();
+    let is_tree_view = app.is_tree_view();
+    // The tree view shows the entries inside the years pane, which gets the
+    // width of both
+    let (files_rect, years_rect, entries_rect) = if is_tree_view {
+        let [files_rect, years_rect] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Ratio(ratios[0], ratios_sum),
+                Constraint::Ratio(ratios[1] + ratios[2], ratios_sum),
+            ])
+            .areas(main_rect);
+        (files_rect, years_rect, None)
+    } else {
+        let [files_rect, years_rect, entries_rect] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(ratios.map(|ratio| Constraint::Ratio(ratio, ratios_sum)))
+            .areas(main_rect);
+        (files_rect, years_rect, Some(entries_rect))
+    };
 
     let files_width = files_rect.width.saturating_sub(2) as usize; // Account for block borders
     let file_display_amount: std::borrow::Cow<'_, str> = match app.view_mode {
@@ -644,16 +987,24 @@ fn ui(frame: &mut Frame, app: &mut App) {
                 .display(app.report.debit_credit.credit_width()),
         ),
     };
+    let format_options = app.config.formatting.format_options();
+    let fraction_separator = app
+        .config
+        .tui
+        .dim_fractions
+        .then_some(format_options.decimal_separator);
     let files = app.files.iter().enumerate().map(|(i, file)| {
         ListItem::new(make_line(
             &file.name,
             if i == app.selection.file {
-                file_display_amount.as_ref()
+                amount_line(file_display_amount.as_ref(), fraction_separator)
             } else {
-                ""
+                Line::default()
             },
+            Style::default(),
             i == app.selection.file,
             app.focus == Focus::Files && app.popup.mode == PopupMode::None,
+            false,
             files_width,
         ))
     });
@@ -665,53 +1016,110 @@ fn ui(frame: &mut Frame, app: &mut App) {
 
     // Years list (middle column)
     let years_width = years_rect.width.saturating_sub(2) as usize; // Account for block borders
-    let years_list = List::new(app.report.year_reports.iter().enumerate().map(|(i, year)| {
-        let amount: std::borrow::Cow<'_, str> = match app.view_mode {
-            ViewMode::Total => std::borrow::Cow::Borrowed(year.subtotal_amount.as_str()),
-            ViewMode::DebitCredit => std::borrow::Cow::Owned(
-                year.subtotal_debit_credit
-                    .display(app.report.year_credit_width),
-            ),
-        };
-        ListItem::new(make_line(
-            &year.title,
-            amount,
-            i == app.selection.year,
-            app.focus == Focus::Years && app.popup.mode == PopupMode::None,
-            years_width,
-        ))
-    }))
-    .block(make_block(&app.report.title, has_focus(Focus::Years)));
+    let is_years_focused = app.focus == Focus::Years && app.popup.mode == PopupMode::None;
+    let today = app.clock.today();
+    let year_items: Vec<ListItem> = if is_tree_view {
+        let rows = app.tree_rows();
+        let selected_row = app.selected_tree_row(&rows);
+        rows.iter()
+            .enumerate()
+            .map(|(i, &(year_index, entry))| {
+                let year = &app.report.year_reports[year_index];
+                ListItem::new(match entry {
+                    None => make_line(
+                        &year.title,
+                        amount_line(year_amount(app, year), fraction_separator),
+                        Style::default(),
+                        i == selected_row,
+                        is_years_focused,
+                        false,
+                        years_width,
+                    ),
+                    Some(entry) => {
+                        let (date, amount) = &year.lines[entry];
+                        let entry = &year.entries[entry];
+                        make_line(
+                            format!("  {date}"),
+                            amount_line(
+                                entry_amount(app, entry, amount, &format_options),
+                                fraction_separator,
+                            ),
+                            date_highlight_style(entry.date, today),
+                            i == selected_row,
+                            is_years_focused,
+                            false,
+                            years_width,
+                        )
+                    }
+                })
+            })
+            .collect()
+    } else {
+        app.report
+            .year_reports
+            .iter()
+            .enumerate()
+            .map(|(i, year)| {
+                ListItem::new(make_line(
+                    &year.title,
+                    amount_line(year_amount(app, year), fraction_separator),
+                    Style::default(),
+                    i == app.selection.year,
+                    is_years_focused,
+                    false,
+                    years_width,
+                ))
+            })
+            .collect()
+    };
+    let years_list =
+        List::new(year_items).block(make_block(&app.report.title, has_focus(Focus::Years)));
 
     frame.render_stateful_widget(years_list, years_rect, &mut ListState::default());
 
-    // Entries list (right column)
-    let entries_width = entries_rect.width.saturating_sub(2) as usize; // Account for block borders
-    let selected_year = &app.report.year_reports[app.selection.year];
-    let entries_list = List::new(selected_year.lines.iter().enumerate().map(
-        |(i, (date, amount))| {
+    // Entries list (right column), only in the panes view
+    if let Some(entries_rect) = entries_rect {
+        let entries_width = entries_rect.width.saturating_sub(2) as usize; // Account for block borders
+        // A file that failed to load has no years
+        let (year_title, lines, entries) = match app.report.year_reports.get(app.selection.year) {
+            Some(year) => (year.title.as_str(), &year.lines[..], &year.entries[..]),
+            None => ("", &[][..], &[][..]),
+        };
+        let entries_list = List::new(lines.iter().enumerate().map(|(i, (date, amount))| {
+            let amount = entry_amount(app, &entries[i], amount, &format_options);
             ListItem::new(make_line(
                 date,
-                amount,
+                amount_line(amount, fraction_separator),
+                date_highlight_style(entries[i].date, today),
                 i == app.selection.entry,
                 app.focus == Focus::YearDetails && app.popup.mode == PopupMode::None,
+                app.config.tui.zebra_stripes && i % 2 == 1,
                 entries_width,
             ))
-        },
-    ))
-    .block(make_block(
-        &selected_year.title,
-        has_focus(Focus::YearDetails),
-    ));
+        }))
+        .block(make_block(year_title, has_focus(Focus::YearDetails)));
 
-    frame.render_stateful_widget(entries_list, entries_rect, &mut ListState::default());
+        frame.render_stateful_widget(entries_list, entries_rect, &mut ListState::default());
+    }
 
+    let keys = &app.config.keys;
     let footer_text = if app.popup.mode == PopupMode::None {
-        "↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit"
+        format!(
+            "↓({})/↑({}): Navigate | Tab: Focus | {}/{}: New/Edit Entry | {}: View | {}: Quit",
+            keys.next, keys.prev, keys.add, keys.edit, keys.view, keys.quit
+        )
+    } else if matches!(app.popup.mode, PopupMode::Info | PopupMode::Error) {
+        String::from("q/Enter: Close")
+    } else if app.popup.mode == PopupMode::Goto {
+        String::from("Enter: Go | q: Cancel")
     } else {
-        "Tab: Switch Field | Enter: Save | q: Cancel"
+        String::from("Tab: Switch Field | Enter: Save | q: Cancel")
     };
-    let footer = Paragraph::new(footer_text).block(Block::default().borders(Borders::ALL));
+    let mut footer_block = Block::default().borders(Borders::ALL);
+    if app.added_entries > 0 {
+        footer_block = footer_block.title(format!(" Added: {} this session ", app.added_entries));
+    }
+    let footer = Paragraph::new(footer_text).block(footer_block);
     frame.render_widget(footer, help_rect);
 
     // Render popup if active
@@ -750,6 +1158,9 @@ fn render_popup(frame: &mut Frame, app: &App) {
     let title = match app.popup.mode {
         PopupMode::AddEntry => " Add New Entry ",
         PopupMode::EditEntry => " Edit Entry ",
+        PopupMode::Info => " Entry ",
+        PopupMode::Error => " Error ",
+        PopupMode::Goto => " Go to Date ",
         PopupMode::None => "",
     };
 
@@ -761,6 +1172,16 @@ fn render_popup(frame: &mut Frame, app: &App) {
 
     let inner_area = popup_block.inner(popup_rect);
     frame.render_widget(popup_block, popup_rect);
+
+    if app.popup.mode == PopupMode::Error {
+        let message = app.popup.error_message.as_deref().unwrap_or_default();
+        let error = Paragraph::new(message)
+            .style(Style::default().fg(Color::Red))
+            .block(Block::default().padding(Padding::horizontal(1)))
+            .wrap(Wrap { trim: true });
+        frame.render_widget(error, inner_area);
+        return;
+    }
     let [file_name_rect, _, date_rect, amount_rect, error_rect, _] = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -776,7 +1197,25 @@ fn render_popup(frame: &mut Frame, app: &App) {
     // File name
     let file = &app.files[app.selection.file];
     let file_name_input = Input::new(file.name.clone());
-    render_input_field(frame, "File  ", &file_name_input, file_name_rect, false);
+    render_input_field(
+        frame,
+        "File  ",
+        &file_name_input,
+        file_name_rect,
+        false,
+        None,
+    );
+
+    if app.popup.mode == PopupMode::Info {
+        // Read-only details of the selected entry as stored in the file
+        if let Some(entry) = app.get_selected_entry() {
+            let date_input = Input::new(entry.date.to_string());
+            render_input_field(frame, "Date  ", &date_input, date_rect, false, None);
+            let amount_input = Input::new(entry.amount.to_string());
+            render_input_field(frame, "Amount", &amount_input, amount_rect, false, None);
+        }
+        return;
+    }
 
     // Date field
     render_input_field(
@@ -785,16 +1224,30 @@ fn render_popup(frame: &mut Frame, app: &App) {
         &app.popup.date_input,
         date_rect,
         app.popup.focus == PopupFocus::Date,
+        None,
     );
 
-    // Amount field
-    render_input_field(
-        frame,
-        "Amount",
-        &app.popup.amount_input,
-        amount_rect,
-        app.popup.focus == PopupFocus::Amount,
-    );
+    // Amount field, with the value of an arithmetic expression next to it
+    if app.popup.mode != PopupMode::Goto {
+        let evaluated = parse_amount(app.popup.amount_input.value())
+            .is_err()
+            .then(|| app.popup_amount().ok())
+            .flatten()
+            .map(|value| {
+                format!(
+                    "= {}",
+                    value.format(&app.config.formatting.format_options())
+                )
+            });
+        render_input_field(
+            frame,
+            "Amount",
+            &app.popup.amount_input,
+            amount_rect,
+            app.popup.focus == PopupFocus::Amount,
+            evaluated.as_deref(),
+        );
+    }
 
     // Error message
     if let Some(error_msg) = &app.popup.error_message {
@@ -813,6 +1266,7 @@ fn render_input_field(
     input: &Input,
     layout: Rect,
     is_focused: bool,
+    hint: Option<&str>,
 ) {
     let style = if is_focused {
         Style::default()
@@ -826,21 +1280,65 @@ fn render_input_field(
     } else {
         Span::raw(" ")
     };
-    let value_span = Span::raw(input.value());
-    let value_width = value_span.width() as u16;
-    let line = Line::from(vec![prefix, Span::raw(name), Span::raw("  "), value_span]).style(style);
-    let line_width = line.width() as u16;
-    frame.render_widget(line, layout);
+    let label = Line::from(vec![prefix, Span::raw(name), Span::raw("  ")]).style(style);
+    let label_width = label.width() as u16;
+    frame.render_widget(label, layout);
+
+    // Long values scroll horizontally, keeping one column free for the cursor
+    // at the end of the value
+    let hint_width = hint.map_or(0, |hint| display_width(hint) as u16 + 1);
+    let [_, value_rect, hint_rect] = Layout::horizontal([
+        Constraint::Length(label_width),
+        Constraint::Fill(1),
+        Constraint::Length(hint_width),
+    ])
+    .areas(layout);
+    let scroll = input.visual_scroll(value_rect.width.saturating_sub(1) as usize);
+    let value = Paragraph::new(input.value())
+        .style(style)
+        .scroll((0, scroll as u16));
+    frame.render_widget(value, value_rect);
+    if let Some(hint) = hint {
+        let hint = Paragraph::new(hint)
+            .style(style.add_modifier(Modifier::DIM))
+            .right_aligned();
+        frame.render_widget(hint, hint_rect);
+    }
 
     if is_focused {
-        let cursor_pos = input.visual_cursor() as u16;
+        let cursor_pos = (input.visual_cursor() - scroll) as u16;
         frame.set_cursor_position(CursorPosition {
-            x: layout.x + line_width - value_width + cursor_pos,
+            x: value_rect.x + cursor_pos,
             y: layout.y,
         });
     }
 }
 
+/// Subtotal of the year as the view mode shows it.
+fn year_amount<'a>(app: &'a App, year: &'a YearReportViewModel) -> std::borrow::Cow<'a, str> {
+    match app.view_mode {
+        ViewMode::Total => std::borrow::Cow::Borrowed(year.subtotal_amount.as_str()),
+        ViewMode::DebitCredit => std::borrow::Cow::Owned(
+            year.subtotal_debit_credit
+                .display(app.report.year_credit_width),
+        ),
+    }
+}
+
+/// Formatted amount of the entry, without its sign for absolute amounts.
+fn entry_amount<'a>(
+    app: &App,
+    entry: &Entry,
+    formatted: &'a str,
+    format_options: &FormatOptions,
+) -> std::borrow::Cow<'a, str> {
+    if app.absolute_amounts {
+        std::borrow::Cow::Owned(entry.amount.abs().format(format_options))
+    } else {
+        std::borrow::Cow::Borrowed(formatted)
+    }
+}
+
 fn make_block(title: &str, is_focused: bool) -> Block<'_> {
     let line = Line::raw(format!(" {title} "));
     Block::default()
@@ -857,11 +1355,25 @@ fn make_block(title: &str, is_focused: bool) -> Block<'_> {
         })
 }
 
+/// Characters the amount field accepts; operators allow arithmetic like `120 / 3`.
+fn is_amount_char(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '.' | '-' | '_' | ' ' | '+' | '*' | '/' | '(' | ')')
+}
+
+/// Text on the system clipboard, if the clipboard is accessible.
+fn clipboard_text() -> Option<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .ok()
+}
+
 fn make_line<'a>(
     left: impl Into<std::borrow::Cow<'a, str>>,
-    right: impl Into<std::borrow::Cow<'a, str>>,
+    right: impl Into<Line<'a>>,
+    left_style: Style,
     is_selected: bool,
     is_focused: bool,
+    is_striped: bool,
     width: usize,
 ) -> Line<'a> {
     let padding_span_left = if is_selected {
@@ -874,17 +1386,21 @@ fn make_line<'a>(
         Span::raw(" ")
     };
     let padding_span_right = Span::raw(" ");
-    let left_span = Span::raw(left);
-    let right_span = Span::raw(right);
-    let spacer = " "
-        .repeat(width.saturating_sub(left_span.width() + right_span.width() + FIXED_PADDING_WIDTH));
-    let line = Line::from(vec![
-        padding_span_left,
-        left_span,
-        Span::raw(spacer),
-        right_span,
-        padding_span_right,
-    ]);
+    let right = right.into();
+    let right_width = right.width();
+    // Keep at least one space between the left text and a non-empty right text
+    let gap_width = usize::from(right_width > 0);
+    let max_left_width = width.saturating_sub(right_width + gap_width + FIXED_PADDING_WIDTH);
+    let left_span = Span::styled(
+        truncate_with_ellipsis(left.into(), max_left_width),
+        left_style,
+    );
+    let spacer =
+        " ".repeat(width.saturating_sub(left_span.width() + right_width + FIXED_PADDING_WIDTH));
+    let mut spans = vec![padding_span_left, left_span, Span::raw(spacer)];
+    spans.extend(right.spans);
+    spans.push(padding_span_right);
+    let line = Line::from(spans);
     if is_selected {
         let bg_color = if is_focused {
             FOCUSED_SELECTION_BG_COLOR
@@ -892,11 +1408,112 @@ fn make_line<'a>(
             UNFOCUSED_SELECTION_BG_COLOR
         };
         line.style(Style::default().bg(bg_color))
+    } else if is_striped {
+        line.style(Style::default().bg(STRIPE_BG_COLOR))
     } else {
         line
     }
 }
 
+/// Splits formatted amounts into spans with the fractional parts, starting at
+/// `decimal_separator`, dimmed. Without a separator the text stays one span.
+fn amount_line<'a>(
+    amount: impl Into<std::borrow::Cow<'a, str>>,
+    decimal_separator: Option<char>,
+) -> Line<'a> {
+    let amount = amount.into();
+    let Some(decimal_separator) = decimal_separator else {
+        return Line::from(amount);
+    };
+    let make_span = |text: String, is_fraction: bool| {
+        if is_fraction {
+            Span::styled(text, Style::default().add_modifier(Modifier::DIM))
+        } else {
+            Span::raw(text)
+        }
+    };
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut in_fraction = false;
+    for ch in amount.chars() {
+        let is_fraction = ch == decimal_separator || (in_fraction && ch.is_ascii_digit());
+        if is_fraction != in_fraction && !text.is_empty() {
+            spans.push(make_span(std::mem::take(&mut text), in_fraction));
+        }
+        in_fraction = is_fraction;
+        text.push(ch);
+    }
+    if !text.is_empty() {
+        spans.push(make_span(text, in_fraction));
+    }
+    Line::from(spans)
+}
+
+/// Index of the name that best matches `query`, ignoring case. An equal name
+/// wins over one containing the query as is, which wins over one that only
+/// contains its characters in order, ties go to the earlier name.
+fn best_fuzzy_match<'a>(names: impl Iterator<Item = &'a str>, query: &str) -> Option<usize> {
+    let names: Vec<&str> = names.collect();
+    if let Some(i) = names.iter().position(|name| *name == query) {
+        return Some(i);
+    }
+    let query = query.to_lowercase();
+    let is_subsequence = |name: &str| {
+        let mut name_chars = name.chars();
+        query
+            .chars()
+            .all(|ch| name_chars.any(|name_ch| name_ch == ch))
+    };
+    let mut first_subsequence = None;
+    for (i, name) in names.into_iter().enumerate() {
+        let name = name.to_lowercase();
+        if name.contains(&query) {
+            return Some(i);
+        }
+        if first_subsequence.is_none() && is_subsequence(&name) {
+            first_subsequence = Some(i);
+        }
+    }
+    first_subsequence
+}
+
+/// Style for an entry's date: today's entries and those of the current month stand out.
+fn date_highlight_style(date: NaiveDate, today: NaiveDate) -> Style {
+    if date == today {
+        Style::default().fg(TODAY_COLOR)
+    } else if date.year() == today.year() && date.month() == today.month() {
+        Style::default().fg(CURRENT_MONTH_COLOR)
+    } else {
+        Style::default()
+    }
+}
+
+fn truncate_with_ellipsis(
+    text: std::borrow::Cow<'_, str>,
+    max_width: usize,
+) -> std::borrow::Cow<'_, str> {
+    if display_width(&text) <= max_width {
+        return text;
+    }
+
+    let ellipsis = '…';
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+    let mut buf = [0; 4];
+    for ch in text.chars() {
+        let ch_width = display_width(ch.encode_utf8(&mut buf));
+        if truncated_width + ch_width + 1 > max_width {
+            break;
+        }
+        truncated.push(ch);
+        truncated_width += ch_width;
+    }
+    if max_width > 0 {
+        truncated.push(ellipsis);
+    }
+    std::borrow::Cow::Owned(truncated)
+}
+
 fn next_index_cycled(current: usize, count: usize) -> usize {
     if current + 1 >= count {
         0
@@ -912,3 +1529,50 @@ fn previous_index_cycled(current: usize, count: usize) -> usize {
         current.saturating_sub(1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use ratatui::backend::TestBackend;
+
+    #[test]
+    fn test_reload_error_after_saving_is_shown() {
+        let tempdir = temp_dir::TempDir::with_prefix("mfinance-tui-test-").unwrap();
+        let path = tempdir.child("expenses.csv");
+        std::fs::write(&path, "date;amount\n2025-01-05;-75.75\n").unwrap();
+        let files = vec![File::new(path.clone()).unwrap()];
+        let today = NaiveDate::from_ymd_opt(2025, 2, 14).unwrap();
+        let mut app = App::new(files, Config::default(), None, Box::new(FixedClock(today)));
+        app.open_add_entry_popup();
+
+        // Another process breaks the file between the save and the reload
+        std::fs::write(&path, "date;amount\n2025-02-30;5\n").unwrap();
+        app.finish_saving();
+
+        let mut terminal = Terminal::new(TestBackend::new(86, 20)).unwrap();
+        terminal.draw(|frame| ui(frame, &mut app)).unwrap();
+        insta::assert_snapshot!(terminal.backend(), @r#"
+        "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌  ─────────────────────────┐"
+        "│▎expenses.csv              ││                          ││                           │"
+        "│                           ││                          ││                           │"
+        "│                           ││                          ││                           │"
+        "│                           ││                          ││                           │"
+        "│                           ││                          ││                           │"
+        "│                ╔ Error ═══════════════════════════════════════════╗                │"
+        "│                ║ CSV error: CSV deserialize error: record 1       ║                │"
+        "│                ║ (line: 2, byte: 12): input is out of range       ║                │"
+        "│                ║                                                  ║                │"
+        "│                ║                                                  ║                │"
+        "│                ║                                                  ║                │"
+        "│                ║                                                  ║                │"
+        "│                ╚══════════════════════════════════════════════════╝                │"
+        "│                           ││                          ││                           │"
+        "│                           ││                          ││                           │"
+        "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+        "┌ Added: 1 this session ─────────────────────────────────────────────────────────────┐"
+        "│q/Enter: Close                                                                      │"
+        "└────────────────────────────────────────────────────────────────────────────────────┘"
+        "#);
+    }
+}
@@ -68,6 +68,294 @@ fn new_entry_with_date_into_existing_file() {
     ");
 }
 
+#[test]
+fn new_entry_defaults_to_today() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["new-entry", "--amount", "42.42"];
+    assert_cmd_snapshot!(Cli::with_args(args).today("2025-02-14").path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+           3 510.42
+              42.42
+    Total: 3 552.84
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-10-01;-200
+    2024-09-11;700
+    2024-10-02;3000.42
+    2025-01-01;10
+    2025-02-14;42.42
+    ");
+}
+
+#[test]
+fn new_entry_concurrent_adds_keep_every_entry() {
+    let test_context = TestContext::new();
+
+    let children: Vec<_> = (1..=8)
+        .map(|amount| {
+            let amount_arg = amount.to_string();
+            let args = vec!["new-entry", "--date", "2024-09-12", "--amount", &amount_arg];
+            let child = Cli::with_args(args)
+                .path(test_context.content_path())
+                .cmd()
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::piped())
+                .spawn()
+                .expect("spawn mfinance");
+            (amount, child)
+        })
+        .collect();
+
+    // A process that finds the file locked fails instead of waiting, every
+    // other one must leave its entry in the file.
+    let mut expected = vec![String::from("date;amount")];
+    for (amount, child) in children {
+        let output = child.wait_with_output().expect("wait for mfinance");
+        if output.status.success() {
+            expected.push(format!("2024-09-12;{amount}"));
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(
+                stderr.contains("File is locked by another process"),
+                "{stderr}"
+            );
+        }
+    }
+    assert!(expected.len() > 1, "no process added its entry");
+
+    let content = test_context.content();
+    let mut lines: Vec<&str> = content.lines().collect();
+    lines.sort();
+    expected.sort();
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn new_entry_amount_from_stdin() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["new-entry", "--amount", "-", "--date", "2025-02-01"];
+    let mut cmd = Cli::with_args(args).path(test_context.content_path()).cmd();
+    assert_cmd_snapshot!(cmd.pass_stdin("  -1 250.50\nignored\n"), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+           3 510.42
+          -1 250.50
+    Total: 2 259.92
+
+    ----- stderr -----
+    ");
+    assert!(test_context.content().ends_with("2025-02-01;-1250.50\n"));
+
+    let args = vec!["new-entry", "--amount", "-"];
+    let mut cmd = Cli::with_args(args).path(test_context.content_path()).cmd();
+    assert_cmd_snapshot!(cmd.pass_stdin("ten\n"), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: Invalid amount: "ten" read from stdin
+    "#);
+}
+
+#[test]
+fn new_entry_batch() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+    let batch_path = test_context.path().join("batch.txt");
+    fs::write(
+        &batch_path,
+        "2025-02-01 -12.50\n# groceries\n\n-1 000\n2025-02-30 5\n2025-02-03 ten\n2025-02-04   7.25\n",
+    )
+    .expect("write batch.txt");
+
+    let args = vec![
+        "new-entry",
+        "--date",
+        "2025-02-02",
+        "--batch",
+        batch_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Added: 3
+           3 510.42
+          -1 005.25
+    Total: 2 505.17
+
+    ----- stderr -----
+    [TEMP_DIR]/batch.txt:5: Invalid date format: 2025-02-30 (input is out of range)
+    [TEMP_DIR]/batch.txt:6: Invalid amount: ten
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-10-01;-200
+    2024-09-11;700
+    2024-10-02;3000.42
+    2025-01-01;10
+    2025-02-01;-12.50
+    2025-02-02;-1000
+    2025-02-04;7.25
+    ");
+}
+
+#[test]
+fn new_entry_batch_without_valid_lines() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+    let batch_path = test_context.path().join("batch.txt");
+    fs::write(&batch_path, "ten\n2025-02-30 5\n").expect("write batch.txt");
+    let content = test_context.content();
+
+    let args = vec!["new-entry", "--batch", batch_path.to_str().unwrap()];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    [TEMP_DIR]/batch.txt:1: Invalid amount: ten
+    [TEMP_DIR]/batch.txt:2: Invalid date format: 2025-02-30 (input is out of range)
+    Error: Found 2 invalid rows
+    ");
+    assert_eq!(test_context.content(), content);
+}
+
+#[test]
+fn new_entry_batch_strict() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+    let batch_path = test_context.path().join("batch.txt");
+    fs::write(&batch_path, "2025-02-01 -12.50\nten\n").expect("write batch.txt");
+
+    let args = vec![
+        "new-entry",
+        "--strict",
+        "--batch",
+        batch_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    [TEMP_DIR]/batch.txt:2: Invalid amount: ten
+    Error: Found 1 invalid rows
+    ");
+    assert!(!test_context.content().contains("2025-02-01"));
+}
+
+#[test]
+fn recur_monthly_entry() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec![
+        "recur", "--amount", "-950", "--from", "2025-01", "--to", "2025-03", "--day", "31",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Added: 3
+         3 510.42
+        -2 850.00
+    Total: 660.42
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-10-01;-200
+    2024-09-11;700
+    2024-10-02;3000.42
+    2025-01-01;10
+    2025-01-31;-950
+    2025-02-28;-950
+    2025-03-31;-950
+    ");
+}
+
+#[test]
+fn new_entry_into_header_only_file() {
+    let test_context = TestContext::new();
+    test_context.setup_empty_test_content();
+
+    let args = vec!["new-entry", "--amount", "42.42", "--date", "2024-09-12"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+            0.00
+           42.42
+    Total: 42.42
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-09-12;42.42
+    ");
+}
+
+#[test]
+fn new_entry_with_grouped_amount() {
+    let test_context = TestContext::new();
+
+    let args = vec![
+        "new-entry",
+        "--amount",
+        "-1_000 000.50",
+        "--date",
+        "2024-09-12",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                    0.00
+           -1 000 000.50
+    Total: -1 000 000.50
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn new_entry_with_malformed_amount_error() {
+    let test_context = TestContext::new();
+
+    let args = vec!["new-entry", "--amount", "1__000", "--date", "2024-09-12"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: invalid value '1__000' for '--amount <AMOUNT>': Invalid amount: 1__000
+
+    For more information, try '--help'.
+    ");
+}
+
 #[test]
 fn new_entry_with_invalid_date_error() {
     let test_context = TestContext::new();
@@ -75,169 +363,2731 @@ fn new_entry_with_invalid_date_error() {
 
     let args = vec!["new-entry", "--amount", "42.42", "--date", "2024-12"];
     assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
-    success: false
-    exit_code: 1
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: Invalid date format: 2024-12 (premature end of input)
+    caused by: premature end of input
+    ");
+}
+
+#[test]
+fn report_without_filter() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700.00
+      2024-10-01:  -200.00
+      2024-10-02: 3 000.42
+      2025-01-01:    10.00
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_filter_year() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--filter", "2024"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2024-09-11:   700.00
+                        2024-10-01:  -200.00
+                        2024-10-02: 3 000.42
+    Total amount for filter '2024': 3 500.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_filter_year_no_entries_error() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--filter", "2020"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries matching filter: 2020
+    ");
+}
+
+#[test]
+fn report_no_total() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--filter", "2024", "--no-total"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    2024-09-11:   700.00
+    2024-10-01:  -200.00
+    2024-10-02: 3 000.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_top() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--top", "2"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-10-02: 3 000.42
+      2024-09-11:   700.00
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_with_indices() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--with-indices"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    1. 2024-09-11:   700.00
+    2. 2024-10-01:  -200.00
+    3. 2024-10-02: 3 000.42
+    4. 2025-01-01:    10.00
+     Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_top_with_indices() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--top", "2", "--with-indices"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    3. 2024-10-02: 3 000.42
+    1. 2024-09-11:   700.00
+     Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_invalid_date_in_file_error() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-10-01;-200\n2024-13-01;700\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: CSV error: CSV deserialize error: record 2 (line: 3, byte: 28): input is out of range
+    caused by: CSV deserialize error: record 2 (line: 3, byte: 28): input is out of range
+    ");
+}
+
+#[test]
+fn report_filter_year_month() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--filter", "2024-10"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                           2024-10-01:  -200.00
+                           2024-10-02: 3 000.42
+    Total amount for filter '2024-10': 2 800.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_filter_year_month_no_entries_error() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--filter", "2020-01"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries matching filter: 2020-01
+    ");
+}
+
+#[test]
+fn report_no_file_error() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: I/O error: Failed to access file: [TEMP_DIR]/test.csv
+    caused by: No such file or directory (os error 2)
+    ");
+}
+
+#[test]
+fn report_no_entries_error() {
+    let test_context = TestContext::new();
+    test_context.setup_empty_test_content();
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries found
+    ");
+}
+
+#[test]
+fn report_new_only_without_marker_shows_everything() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--new-only"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700.00
+      2024-10-01:  -200.00
+      2024-10-02: 3 000.42
+      2025-01-01:    10.00
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_mark_and_new_only() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--filter", "2024", "--mark"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2024-09-11:   700.00
+                        2024-10-01:  -200.00
+                        2024-10-02: 3 000.42
+    Total amount for filter '2024': 3 500.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["new-entry", "--amount", "42.42", "--date", "2024-12-01"];
+    Cli::with_args(args)
+        .path(test_context.content_path())
+        .cmd()
+        .output()
+        .expect("add entry");
+
+    let args = vec!["report", "--new-only", "--mark"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-12-01: 42.42
+      2025-01-01: 10.00
+    Total amount: 52.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--new-only"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries after the last marked report: 2025-01-01
+    ");
+}
+
+#[test]
+fn report_allow_empty() {
+    let test_context = TestContext::new();
+    test_context.setup_empty_test_content();
+
+    let args = vec!["report", "--allow-empty"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Total amount: 0.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_empty_result_ok_from_config() {
+    let test_context = TestContext::new();
+    test_context.setup_empty_test_content();
+    test_context.setup_data_config(r#"empty_result = "ok""#);
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Total amount: 0.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_fail_on_empty_overrides_config() {
+    let test_context = TestContext::new();
+    test_context.setup_empty_test_content();
+    test_context.setup_data_config(r#"empty_result = "ok""#);
+
+    let args = vec!["report", "--fail-on-empty"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries found
+    ");
+}
+
+#[test]
+fn report_filter_allow_empty() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--filter", "2020", "--allow-empty"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Total amount for filter '2020': 0.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_csv_summary_month() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--csv-summary", "month"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    period;total
+    2024-09;700
+    2024-10;2800.42
+    2025-01;10
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_csv_summary_year_with_total() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--csv-summary", "year", "--summary-total"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    period;total
+    2024;3500.42
+    2025;10
+    TOTAL;3510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn sort() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["sort"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-09-11;700
+    2024-10-01;-200
+    2024-10-02;3000.42
+    2025-01-01;10
+    ");
+}
+
+#[test]
+fn sort_fails_when_output_is_locked() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+    let sorted_path = test_context.tempdir.child("sorted.csv");
+    let _lock = mfinance::lock_file(&sorted_path).expect("lock sorted.csv");
+
+    let args = vec!["sort", "--output", sorted_path.to_str().unwrap()];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: File is locked by another process: [TEMP_DIR]/sorted.csv
+    ");
+    assert!(!sorted_path.exists());
+}
+
+#[test]
+fn sort_converts_separator_and_back() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let converted_path = test_context.tempdir.child("converted.csv");
+
+    let args = vec![
+        "sort",
+        "--separator-output",
+        ",",
+        "--output",
+        converted_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(fs::read_to_string(&converted_path).expect("read converted.csv"), @r"
+    date,amount
+    2024-09-11,700
+    2024-10-01,-200
+    2024-10-02,3000.42
+    2025-01-01,10
+    ");
+
+    let args = vec!["sort", "--separator", ","];
+    assert_cmd_snapshot!(Cli::with_args(args).path(&converted_path).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(fs::read_to_string(&converted_path).expect("read converted.csv"), @r"
+    date;amount
+    2024-09-11;700
+    2024-10-01;-200
+    2024-10-02;3000.42
+    2025-01-01;10
+    ");
+}
+
+#[test]
+fn sort_refuses_in_place_separator_conversion() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["sort", "--separator-output", ","];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: the following required arguments were not provided:
+      --output <PATH>
+
+    Usage: mfinance sort --output <PATH> --separator-output <SEPARATOR_OUTPUT> <FILE>
+
+    For more information, try '--help'.
+    ");
+}
+
+#[test]
+fn sort_stamp() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["sort", "--stamp"];
+    assert_cmd_snapshot!(Cli::with_args(args).today("2025-02-14").path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    let mut settings = insta::Settings::clone_current();
+    settings.add_filter(env!("CARGO_PKG_VERSION"), "[VERSION]");
+    settings.bind(|| {
+        assert_snapshot!(test_context.content(), @r"
+        # generated by mfinance v[VERSION] on 2025-02-14
+        date;amount
+        2024-09-11;700
+        2024-10-01;-200
+        2024-10-02;3000.42
+        2025-01-01;10
+        ");
+    });
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700.00
+      2024-10-01:  -200.00
+      2024-10-02: 3 000.42
+      2025-01-01:    10.00
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn sort_backup() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let original = test_context.content();
+
+    let args = vec!["sort", "--backup"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    let backup_path = test_context.tempdir.child("test.csv.bak");
+    assert_eq!(
+        fs::read_to_string(&backup_path).expect("read backup"),
+        original
+    );
+
+    // Without overwriting, the first backup stays and the next one is numbered
+    test_context.setup_data_config("[backup]\nenabled = true\noverwrite = false\n");
+    let sorted = test_context.content();
+    let args = vec!["sort"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    assert_eq!(
+        fs::read_to_string(&backup_path).expect("read backup"),
+        original
+    );
+    let numbered_path = test_context.tempdir.child("test.csv.bak.1");
+    assert_eq!(
+        fs::read_to_string(&numbered_path).expect("read backup"),
+        sorted
+    );
+}
+
+#[test]
+fn sort_expands_two_digit_years() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n24-10-01;-200\n69-01-01;1\n70-01-01;2\n2023-05-05;10\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["sort", "--two-digit-year-pivot", "70"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    1970-01-01;2
+    2023-05-05;10
+    2024-10-01;-200
+    2069-01-01;1
+    ");
+}
+
+#[test]
+fn sort_blank_as_zero() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-01-02;-5\n2024-01-01;\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: CSV error: CSV deserialize error: record 2 (line: 3, byte: 26): invalid value: string "", expected a Decimal type representing a fixed-point number
+    caused by: CSV deserialize error: record 2 (line: 3, byte: 26): invalid value: string "", expected a Decimal type representing a fixed-point number
+    "#);
+
+    let args = vec!["report", "--blank-as-zero"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-01-01:  0.00
+      2024-01-02: -5.00
+    Total amount: -5.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["sort", "--blank-as-zero"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-01-01;0
+    2024-01-02;-5
+    ");
+}
+
+#[test]
+fn crlf_line_endings_are_kept() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\r\n2024-10-02;3000.42\r\n2024-09-11;700\r\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["new-entry", "--amount", "-200", "--date", "2024-10-01"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+           3 700.42
+            -200.00
+    Total: 3 500.42
+
+    ----- stderr -----
+    ");
+    let args = vec!["sort", "--stamp"];
+    assert_cmd_snapshot!(Cli::with_args(args).today("2025-02-14").path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    let stamp = format!(
+        "# generated by mfinance v{} on 2025-02-14",
+        env!("CARGO_PKG_VERSION")
+    );
+    assert_eq!(
+        test_context.content(),
+        format!(
+            "{stamp}\r\ndate;amount\r\n2024-09-11;700\r\n2024-10-01;-200\r\n2024-10-02;3000.42\r\n"
+        )
+    );
+}
+
+#[test]
+fn sort_rejects_multi_character_separator() {
+    let args = vec!["sort", "--separator", "ab", "file.csv"];
+    assert_cmd_snapshot!(Cli::with_args(args).cmd(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: invalid value 'ab' for '--separator <SEPARATOR>': delimiter must be a single ASCII character, got 'ab'
+
+    For more information, try '--help'.
+    ");
+}
+
+#[test]
+fn sort_profile() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["sort", "--profile"];
+    let mut settings = insta::Settings::clone_current();
+    settings.add_filter(r"profile: (\w+): .+", "profile: $1: [DURATION]");
+    settings.bind(|| {
+        assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+        success: true
+        exit_code: 0
+        ----- stdout -----
+
+        ----- stderr -----
+        profile: config: [DURATION]
+        profile: read: [DURATION]
+        profile: sort: [DURATION]
+        profile: write: [DURATION]
+        ");
+    });
+}
+
+#[test]
+fn migrate_round_trip() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount;full_amount\n2024-10-01;-200;-400.50\n2024-09-11;700.05;\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["migrate", "--to", "cents"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Migrated: 2
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount_cents;full_amount_cents
+    2024-10-01;-20000;-40050
+    2024-09-11;70005;
+    ");
+
+    let args = vec!["migrate", "--to", "decimal"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Migrated: 2
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount;full_amount
+    2024-10-01;-200.00;-400.50
+    2024-09-11;700.05;
+    ");
+}
+
+#[test]
+fn migrate_refuses_fractions_of_cents() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-10-01;-200.125\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["migrate", "--to", "cents"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: Amount has fractions of a cent: -200.125
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-10-01;-200.125
+    ");
+}
+
+#[test]
+fn checksum_ignores_formatting() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let other_path = test_context.path().join("other.csv");
+    fs::write(
+        &other_path,
+        "date;amount_cents\n2024-10-02;300042\n2024-09-11;70000\n2024-10-01;-20000\n2025-01-01;1000\n",
+    )
+    .expect("write other.csv");
+
+    let checksum = |args: Vec<&str>, path: &Path| {
+        let output = Cli::with_args(args).path(path).cmd().output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let unordered = checksum(vec!["checksum"], &test_context.content_path());
+    assert_eq!(unordered, checksum(vec!["checksum"], &other_path));
+    assert_ne!(
+        checksum(vec!["checksum", "--ordered"], &test_context.content_path()),
+        checksum(vec!["checksum", "--ordered"], &other_path)
+    );
+
+    assert_cmd_snapshot!(Cli::with_args(vec!["checksum"]).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    0603f72b3daf9ccd1024188e60eb326ba94e6a26e56801ff11a10ed95ebbc062
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn convert() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let output_path = test_context.tempdir.child("converted.csv");
+
+    let args = vec![
+        "convert",
+        "--rate",
+        "0.92",
+        "--output",
+        output_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Total: 3 229.59
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(fs::read_to_string(&output_path).expect("read converted.csv"), @r"
+    date;amount
+    2024-10-01;-184.00
+    2024-09-11;644.00
+    2024-10-02;2760.39
+    2025-01-01;9.20
+    ");
+}
+
+#[test]
+fn convert_in_place_backup() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let original = test_context.content();
+    let content_path = test_context.content_path();
+
+    let args = vec![
+        "convert",
+        "--backup",
+        "--rate",
+        "2",
+        "--output",
+        content_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Total: 7 020.84
+
+    ----- stderr -----
+    ");
+    let backup_path = test_context.tempdir.child("test.csv.bak");
+    assert_eq!(
+        fs::read_to_string(&backup_path).expect("read backup"),
+        original
+    );
+    assert!(test_context.content().contains("6000.84"));
+}
+
+#[test]
+fn convert_full_amount() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount;full_amount\n2024-01-01;-600;-1200\n2024-01-02;-20;\n",
+    )
+    .expect("write test.csv");
+    let output_path = test_context.tempdir.child("converted.csv");
+
+    let args = vec![
+        "convert",
+        "--rate",
+        "0.5",
+        "--output",
+        output_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Total: -310.00
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(fs::read_to_string(&output_path).expect("read converted.csv"), @r"
+    date;amount;full_amount
+    2024-01-01;-300.0;-600.0
+    2024-01-02;-10.0;
+    ");
+}
+
+#[test]
+fn convert_overflow_error() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let output_path = test_context.tempdir.child("converted.csv");
+
+    let args = vec![
+        "convert",
+        "--rate",
+        "79228162514264337593543950335",
+        "--output",
+        output_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: Amount -200 overflows when multiplied by 79228162514264337593543950335
+    ");
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn dates() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-10-02;-5\n2024-09-11;700\n2024-10-02;3000.42\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["dates"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    2024-09-11: 1
+    2024-10-02: 2
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn dates_json() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["dates", "--format", "json"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    [
+      {
+        "count": 1,
+        "date": "2024-09-11"
+      },
+      {
+        "count": 1,
+        "date": "2024-10-01"
+      },
+      {
+        "count": 1,
+        "date": "2024-10-02"
+      },
+      {
+        "count": 1,
+        "date": "2025-01-01"
+      }
+    ]
+
+    ----- stderr -----
+    "#);
+}
+
+#[test]
+fn archive() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let archive_path = test_context.tempdir.child("archive.csv");
+    fs::write(&archive_path, "date;amount\n2023-05-01;1\n").expect("write archive.csv");
+
+    let args = vec![
+        "archive",
+        "--before",
+        "2024-10-02",
+        "--into",
+        archive_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Archived: 2
+    Remaining: 2
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-10-02;3000.42
+    2025-01-01;10
+    ");
+    assert_snapshot!(fs::read_to_string(&archive_path).unwrap(), @r"
+    date;amount
+    2023-05-01;1
+    2024-09-11;700
+    2024-10-01;-200
+    ");
+}
+
+#[test]
+fn archive_fails_when_into_file_is_locked() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+    let original = test_context.content();
+    let archive_path = test_context.tempdir.child("archive.csv");
+    fs::write(&archive_path, "date;amount\n2023-05-01;1\n").expect("write archive.csv");
+    let _lock = mfinance::lock_file(&archive_path).expect("lock archive.csv");
+
+    let args = vec![
+        "archive",
+        "--before",
+        "2024-10-02",
+        "--into",
+        archive_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: File is locked by another process: [TEMP_DIR]/archive.csv
+    ");
+    assert_eq!(test_context.content(), original);
+}
+
+#[test]
+fn archive_into_new_file() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    let archive_path = test_context.tempdir.child("archive.csv");
+
+    let args = vec![
+        "archive",
+        "--before",
+        "2025-01-01",
+        "--into",
+        archive_path.to_str().unwrap(),
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Archived: 3
+    Remaining: 1
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2025-01-01;10
+    ");
+    assert_snapshot!(fs::read_to_string(&archive_path).unwrap(), @r"
+    date;amount
+    2024-09-11;700
+    2024-10-01;-200
+    2024-10-02;3000.42
+    ");
+}
+
+#[test]
+fn report_amount_in_cents() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount_cents\n2024-01-01;10050\n2024-01-02;-5\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-01-01: 100.50
+      2024-01-02:  -0.05
+    Total amount: 100.45
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn new_entry_and_sort_keep_amount_in_cents() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount_cents\n2024-01-02;10050\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["new-entry", "--amount", "-0.99", "--date", "2024-01-01"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+          100.50
+           -0.99
+    Total: 99.51
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["sort"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount_cents
+    2024-01-01;-99
+    2024-01-02;10050
+    ");
+}
+
+#[test]
+fn new_entry_refuses_fractions_of_cents_in_cents_file() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount_cents\n2024-01-02;10050\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["new-entry", "--amount", "1.005", "--date", "2024-01-03"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: Amount has fractions of a cent: 1.005
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount_cents
+    2024-01-02;10050
+    ");
+}
+
+#[test]
+fn report_each_amount_column() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount;full_amount\n2024-01-01;-600;-1200\n2024-01-02;-20;\n2024-01-03;-50;-150\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-01-01: -600.00
+      2024-01-02:  -20.00
+      2024-01-03:  -50.00
+    Total amount: -670.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--column", "full-amount"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-01-01: -1 200.00
+      2024-01-02:    -20.00
+      2024-01-03:   -150.00
+    Total amount: -1 370.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn new_entry_and_sort_keep_full_amount_column() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount;full_amount\n2024-01-02;-600;-1200\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["new-entry", "--amount", "-20", "--date", "2024-01-01"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+           -600.00
+            -20.00
+    Total: -620.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["sort"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    ");
+
+    assert_snapshot!(test_context.content(), @r"
+    date;amount;full_amount
+    2024-01-01;-20;
+    2024-01-02;-600;-1200
+    ");
+}
+
+#[test]
+fn report_locale_from_env() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--locale-from-env"];
+    assert_cmd_snapshot!(Cli::with_args(args).numeric_locale("de_DE.UTF-8").path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700,00
+      2024-10-01:  -200,00
+      2024-10-02: 3.000,42
+      2025-01-01:    10,00
+    Total amount: 3.510,42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_locale_from_env_config_takes_precedence() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        thousands_separator = " "
+        "#,
+    );
+
+    let args = vec!["report", "--locale-from-env"];
+    assert_cmd_snapshot!(Cli::with_args(args).numeric_locale("de_DE.UTF-8").path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700,00
+      2024-10-01:  -200,00
+      2024-10-02: 3 000,42
+      2025-01-01:    10,00
+    Total amount: 3 510,42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_locale_from_env_unknown_locale() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--locale-from-env", "--filter", "2025"];
+    assert_cmd_snapshot!(Cli::with_args(args).numeric_locale("C").path(test_context.content_path()).cmd(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2025-01-01: 10.00
+    Total amount for filter '2025': 10.00
+
+    ----- stderr -----
+    Warning: Unknown number format of locale "C"
+    "#);
+}
+
+#[test]
+fn export_ledger() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["export", "--format", "ledger"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    2024-09-11 test
+        Assets:test  700.00
+        Equity:Unassigned
+
+    2024-10-01 test
+        Assets:test  -200.00
+        Equity:Unassigned
+
+    2024-10-02 test
+        Assets:test  3000.42
+        Equity:Unassigned
+
+    2025-01-01 test
+        Assets:test  10.00
+        Equity:Unassigned
+
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_group_by_quarter() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--group-by", "quarter"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+         2024-Q3:   700.00  (1 entry)
+         2024-Q4: 2 800.42  (2 entries)
+         2025-Q1:    10.00  (1 entry)
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_with_wide_characters() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        currency_symbol = "円"
+        currency_position = "Suffix"
+        "#,
+    );
+
+    let args = vec![
+        "report",
+        "--title",
+        "十月の支出",
+        "--opening-balance",
+        "-1000",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+           十月の支出
+      2024-09-11:   700.00円   -300.00円
+      2024-10-01:  -200.00円   -500.00円
+      2024-10-02: 3 000.42円  2 500.42円
+      2025-01-01:    10.00円  2 510.42円
+    Total amount: 2 510.42円
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_with_title() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--title", "Expenses"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+           Expenses
+      2024-09-11:   700.00
+      2024-10-01:  -200.00
+      2024-10-02: 3 000.42
+      2025-01-01:    10.00
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec![
+        "report",
+        "--filter",
+        "2025",
+        "--title",
+        "A title that is much longer than the report itself",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    A title that is much longer than the report itself
+                        2025-01-01: 10.00
+    Total amount for filter '2025': 10.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_negatives() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--negatives"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-10-01: -200.00
+    Total amount: -200.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_positives() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--positives"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700.00
+      2024-10-02: 3 000.42
+      2025-01-01:    10.00
+    Total amount: 3 710.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_negatives_conflicts_with_positives() {
+    let args = vec!["report", "--negatives", "--positives", "file.csv"];
+    assert_cmd_snapshot!(Cli::with_args(args).cmd(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: the argument '--negatives' cannot be used with '--positives'
+
+    Usage: mfinance report --negatives <FILE>
+
+    For more information, try '--help'.
+    ");
+}
+
+#[test]
+fn report_opening_balance() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--opening-balance", "-1000"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700.00   -300.00
+      2024-10-01:  -200.00   -500.00
+      2024-10-02: 3 000.42  2 500.42
+      2025-01-01:    10.00  2 510.42
+    Total amount: 2 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_running_min_max() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--running-min-max", "--opening-balance", "-1000"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700.00   -300.00  min -300.00  max  -300.00
+      2024-10-01:  -200.00   -500.00  min -500.00  max  -300.00
+      2024-10-02: 3 000.42  2 500.42  min -500.00  max 2 500.42
+      2025-01-01:    10.00  2 510.42  min -500.00  max 2 510.42
+    Total amount: 2 510.42
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_group_by_day() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-10-01;-200\n2024-10-02;-15.50\n2024-10-01;1000\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report", "--group-by", "day"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-10-01: 800.00  (2 entries)
+      2024-10-02: -15.50  (1 entry)
+    Total amount: 784.50
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_group_by_month_deltas() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--group-by", "month", "--fill-gaps", "--deltas"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+         2024-09:   700.00  (1 entry)            -         -
+         2024-10: 2 800.42  (2 entries)  +2 100.42  +300.06%
+         2024-11:     0.00  (0 entries)  -2 800.42  -100.00%
+         2024-12:     0.00  (0 entries)       0.00       n/a
+         2025-01:    10.00  (1 entry)       +10.00       n/a
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec![
+        "report",
+        "--group-by",
+        "month",
+        "--deltas",
+        "--format",
+        "json",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    {
+      "filter": null,
+      "formatted_total": "3 510.42",
+      "groups": [
+        {
+          "change": null,
+          "change_percent": null,
+          "count": 1,
+          "formatted_total": "700.00",
+          "period": "2024-09",
+          "total": "700"
+        },
+        {
+          "change": "2100.42",
+          "change_percent": "300.06",
+          "count": 2,
+          "formatted_total": "2 800.42",
+          "period": "2024-10",
+          "total": "2800.42"
+        },
+        {
+          "change": "-2790.42",
+          "change_percent": "-99.64",
+          "count": 1,
+          "formatted_total": "10.00",
+          "period": "2025-01",
+          "total": "10"
+        }
+      ],
+      "total": "3510.42"
+    }
+
+    ----- stderr -----
+    "#);
+}
+
+#[test]
+fn report_deltas_markdown_conflict() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec![
+        "report",
+        "--group-by",
+        "month",
+        "--deltas",
+        "--format",
+        "markdown",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 2
+    ----- stdout -----
+
+    ----- stderr -----
+    error: --deltas can't be used with --format markdown
+
+    Usage: mfinance [OPTIONS] <COMMAND>
+
+    For more information, try '--help'.
+    ");
+}
+
+#[test]
+fn report_group_by_month_fill_gaps() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--group-by", "month", "--fill-gaps"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+         2024-09:   700.00  (1 entry)
+         2024-10: 2 800.42  (2 entries)
+         2024-11:     0.00  (0 entries)
+         2024-12:     0.00  (0 entries)
+         2025-01:    10.00  (1 entry)
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--csv-summary", "month", "--fill-gaps"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    period;total
+    2024-09;700
+    2024-10;2800.42
+    2024-11;0
+    2024-12;0
+    2025-01;10
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_group_by_quarter_json() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--group-by", "quarter", "--format", "json"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r#"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    {
+      "filter": null,
+      "formatted_total": "3 510.42",
+      "groups": [
+        {
+          "count": 1,
+          "formatted_total": "700.00",
+          "period": "2024-Q3",
+          "total": "700"
+        },
+        {
+          "count": 2,
+          "formatted_total": "2 800.42",
+          "period": "2024-Q4",
+          "total": "2800.42"
+        },
+        {
+          "count": 1,
+          "formatted_total": "10.00",
+          "period": "2025-Q1",
+          "total": "10"
+        }
+      ],
+      "total": "3510.42"
+    }
+
+    ----- stderr -----
+    "#);
+}
+
+#[test]
+fn report_markdown() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--format", "markdown"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    | Date | Amount |
+    | --- | ---: |
+    | 2024-09-11 | 700.00 |
+    | 2024-10-01 | -200.00 |
+    | 2024-10-02 | 3 000.42 |
+    | 2025-01-01 | 10.00 |
+    | **Total** | **3 510.42** |
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--format", "markdown", "--group-by", "year"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    | Period | Entries | Amount |
+    | --- | ---: | ---: |
+    | 2024 | 3 | 3 500.42 |
+    | 2025 | 1 | 10.00 |
+    | **Total** | **4** | **3 510.42** |
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn doctor() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    fs::write(
+        test_context.path().join("broken.csv"),
+        "date;amount\n2024-13-01;10\n2024-01-02;5\n2024-01-03;ten\n2024-01-04\n",
+    )
+    .expect("write broken.csv");
+
+    let args = vec!["doctor"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    broken.csv:2: input is out of range
+    broken.csv:4: invalid value: string "ten", expected a Decimal type representing a fixed-point number
+    broken.csv:5: CSV error: record 4 (line: 5, byte: 54): found record with 1 fields, but the previous record has 2 fields
+    test.csv: OK
+
+    ----- stderr -----
+    Error: Found 3 invalid rows
+    "#);
+}
+
+#[test]
+fn new_entry_fails_when_file_is_locked() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+    let _lock = mfinance::lock_file(&test_context.content_path()).expect("lock test.csv");
+
+    let args = vec!["new-entry", "--amount", "5"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: File is locked by another process: [TEMP_DIR]/test.csv
+    ");
+    assert!(
+        fs::read_to_string(test_context.content_path())
+            .expect("read test.csv")
+            .ends_with("2025-01-01;10\n")
+    );
+}
+
+#[test]
+fn report_bom_prefixed_file() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "\u{feff}date;amount\n2024-10-01;-200\n2024-09-11;700\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:  700.00
+      2024-10-01: -200.00
+    Total amount:  500.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_bom_prefixed_file_pretty_errors() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    fs::write(
+        test_context.content_path(),
+        "\u{feff}date;amount\n2024-13-01;-200\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report", "--pretty-errors"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: CSV error: CSV deserialize error: record 1 (line: 2, byte: 15): input is out of range
+    caused by: CSV deserialize error: record 1 (line: 2, byte: 15): input is out of range
+     --> [TEMP_DIR]/test.csv:2
+      |
+    2 | 2024-13-01;-200
+      | ^^^^^^^^^^
+    ");
+}
+
+#[test]
+fn report_as_of() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec![
+        "report",
+        "--as-of",
+        "2024-10-01",
+        "--opening-balance",
+        "100",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:  700.00  800.00
+      2024-10-01: -200.00  600.00
+    Total amount:  600.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--as-of", "2024-01-01"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries found
+    ");
+}
+
+#[test]
+fn report_reconcile_rounding() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2025-01-01;0.005\n2025-01-02;0.005\n2025-01-03;0.005\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2025-01-01: 0.00
+      2025-01-02: 0.00
+      2025-01-03: 0.00
+    Total amount: 0.02
+
+    ----- stderr -----
+    ");
+
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        reconcile_rounding = true
+        "#,
+    );
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2025-01-01: 0.01
+      2025-01-02: 0.01
+      2025-01-03: 0.00
+    Total amount: 0.02
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_total_to_stdout() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--total-to-stdout"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    3510.42
+
+    ----- stderr -----
+    2024-09-11:   700.00
+    2024-10-01:  -200.00
+    2024-10-02: 3 000.42
+    2025-01-01:    10.00
+    ");
+}
+
+#[test]
+fn report_total_to_stdout_rounds() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2025-01-01;10.005\n2025-01-02;0.01\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report", "--total-to-stdout"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    10.02
+
+    ----- stderr -----
+    2025-01-01: 10.00
+    2025-01-02:  0.01
+    ");
+}
+
+#[test]
+fn report_percent() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2025-01-01;50\n2025-01-02;30\n2025-01-03;20\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report", "--percent"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2025-01-01:  50.00%
+      2025-01-02:  30.00%
+      2025-01-03:  20.00%
+    Total amount: 100.00%
+    Sums to 100%?     yes
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--percent", "--exclude-filter", "2025-01-03"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                             2025-01-01: 50.00%
+                             2025-01-02: 30.00%
+    Total amount excluding '2025-01-03': 80.00%
+                           Sums to 100%?     no
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_sort_output() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--sort-output", "amount", "--desc"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-10-02: 3 000.42
+      2024-09-11:   700.00
+      2025-01-01:    10.00
+      2024-10-01:  -200.00
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--filter", "2024", "--sort-output", "amount"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2024-10-01:  -200.00
+                        2024-09-11:   700.00
+                        2024-10-02: 3 000.42
+    Total amount for filter '2024': 3 500.42
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-10-01;-200
+    2024-09-11;700
+    2024-10-02;3000.42
+    2025-01-01;10
+    ");
+}
+
+#[test]
+fn report_exclude_filter() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--exclude-filter", "2025"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                       2024-09-11:   700.00
+                       2024-10-01:  -200.00
+                       2024-10-02: 3 000.42
+    Total amount excluding '2025': 3 500.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--filter", "2024", "--exclude-filter", "2024-10"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                                            2024-09-11: 700.00
+    Total amount for filter '2024' excluding '2024-10': 700.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--filter", "2025", "--exclude-filter", "2025-01"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries matching filter: 2025 excluding 2025-01
+    ");
+}
+
+#[test]
+fn report_exclude_weekends() {
+    let test_context = TestContext::new();
+    // Friday to Monday
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2025-02-14;-10\n2025-02-15;-20\n2025-02-16;-30\n2025-02-17;-40\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report", "--exclude-weekends"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2025-02-14: -10.00
+      2025-02-17: -40.00
+    Total amount: -50.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--weekends-only"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2025-02-15: -20.00
+      2025-02-16: -30.00
+    Total amount: -50.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--weekends-only", "--filter", "2025-02-14"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: No entries found
+    ");
+}
+
+#[test]
+fn report_after_before() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+
+    let args = vec!["report", "--after", "2024-10-01"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-10-02: 3 000.42
+      2025-01-01:    10.00
+    Total amount: 3 010.42
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--before", "2024-10-02"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:  700.00
+      2024-10-01: -200.00
+    Total amount:  500.00
+
+    ----- stderr -----
+    ");
+
+    let args = vec![
+        "report",
+        "--filter",
+        "2024",
+        "--after",
+        "2024-09-11",
+        "--before",
+        "2024-10-02",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2024-10-01: -200.00
+    Total amount for filter '2024': -200.00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_net_by_file() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    fs::write(
+        test_context.path().join("expenses.csv"),
+        "date;amount\n2024-10-01;-20000\n2024-10-02;-15.50\n",
+    )
+    .expect("write expenses.csv");
+    fs::write(test_context.path().join("empty.csv"), "date;amount\n").expect("write empty.csv");
+    fs::write(
+        test_context.path().join("食費.csv"),
+        "date;amount\n2024-10-01;-30\n",
+    )
+    .expect("write 食費.csv");
+
+    let args = vec!["report", "--net-by-file"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+       empty.csv:       0.00
+    expenses.csv: -20 015.50
+        test.csv:   3 510.42
+        食費.csv:     -30.00
+    Total amount: -16 535.08
+
+    ----- stderr -----
+    ");
+
+    let args = vec!["report", "--net-by-file", "--sort"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+        test.csv:   3 510.42
+       empty.csv:       0.00
+        食費.csv:     -30.00
+    expenses.csv: -20 015.50
+    Total amount: -16 535.08
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn report_pretty_errors() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-01-02;5\n2024-01-03;ten\n",
+    )
+    .expect("write test.csv");
+
+    let args = vec!["report", "--pretty-errors"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r#"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: CSV error: CSV deserialize error: record 2 (line: 3, byte: 25): invalid value: string "ten", expected a Decimal type representing a fixed-point number
+    caused by: CSV deserialize error: record 2 (line: 3, byte: 25): invalid value: string "ten", expected a Decimal type representing a fixed-point number
+     --> [TEMP_DIR]/test.csv:3
+      |
+    3 | 2024-01-03;ten
+      |            ^^^
+    "#);
+}
+
+#[test]
+fn tui_file_not_found() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+    fs::write(test_context.path().join("other.csv"), "date;amount\n").expect("write other.csv");
+
+    let args = vec!["tui", "--file", "missing.csv"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: I/O error: No file named missing.csv in directory: [TEMP_DIR], available files: other.csv, test.csv
+    caused by: No such file
+    ");
+}
+
+#[test]
+fn completions_bash() {
+    let output = Cli::with_args(vec!["completions", "bash"])
+        .cmd()
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let script = String::from_utf8(output.stdout).unwrap();
+    for command in ["new-entry", "report", "sort", "tui", "config"] {
+        assert!(script.contains(command), "no {command} in the script");
+    }
+}
+
+#[test]
+fn init() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+
+    let args = vec!["init"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Created [TEMP_DIR]/test.csv with 6 example entries
+
+    ----- stderr -----
+    ");
+    assert_snapshot!(test_context.content(), @r"
+    date;amount
+    2024-09-11;1500.00
+    2024-09-15;-42.50
+    2024-10-01;-200.00
+    2024-12-24;-89.99
+    2025-01-01;1500.00
+    2025-01-05;-75.75
+    ");
+}
+
+#[test]
+fn init_refuses_to_overwrite() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_test_content();
+
+    let args = vec!["init"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Error: I/O error: File already exists, use --force to overwrite it: [TEMP_DIR]/test.csv
+    caused by: entity already exists
+    ");
+    assert!(test_context.content().contains("3000.42"));
+
+    let args = vec!["init", "--force"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
     ----- stdout -----
+    Created [TEMP_DIR]/test.csv with 6 example entries
 
     ----- stderr -----
-    Error: Invalid date format: 2024-12 (premature end of input)
-    caused by: premature end of input
     ");
+    assert!(!test_context.content().contains("3000.42"));
 }
 
 #[test]
-fn report_without_filter() {
-    let test_context = TestContext::new();
+fn init_force_backup() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
     test_context.setup_test_content();
+    let original = test_context.content();
 
-    let args = vec!["report"];
+    let args = vec!["init", "--force", "--backup"];
     assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
     success: true
     exit_code: 0
     ----- stdout -----
-      2024-09-11:   700.00
-      2024-10-01:  -200.00
-      2024-10-02: 3 000.42
-      2025-01-01:    10.00
-    Total amount: 3 510.42
+    Created [TEMP_DIR]/test.csv with 6 example entries
 
     ----- stderr -----
     ");
+    let backup_path = test_context.tempdir.child("test.csv.bak");
+    assert_eq!(
+        fs::read_to_string(&backup_path).expect("read backup"),
+        original
+    );
 }
 
 #[test]
-fn report_filter_year() {
+fn return_gain() {
     let test_context = TestContext::new();
     test_context.setup_test_content();
 
-    let args = vec!["report", "--filter", "2024"];
+    let args = vec!["return", "--final", "4000"];
     assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
     success: true
     exit_code: 0
     ----- stdout -----
-                        2024-09-11:   700.00
-                        2024-10-01:  -200.00
-                        2024-10-02: 3 000.42
-    Total amount for filter '2024': 3 500.42
+    Contributions: 3 510.42
+    Final balance: 4 000.00
+             Gain:   489.58
+           Return:   13.95%
 
     ----- stderr -----
     ");
 }
 
 #[test]
-fn report_filter_year_no_entries_error() {
+fn return_loss() {
     let test_context = TestContext::new();
-    test_context.setup_test_content();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-01-01;1000\n2024-02-01;1000\n",
+    )
+    .expect("write test.csv");
 
-    let args = vec!["report", "--filter", "2020"];
+    let args = vec!["return", "--final", "1_900"];
     assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
-    success: false
-    exit_code: 1
+    success: true
+    exit_code: 0
     ----- stdout -----
+    Contributions: 2 000.00
+    Final balance: 1 900.00
+             Gain:  -100.00
+           Return:   -5.00%
 
     ----- stderr -----
-    Error: No entries matching filter: 2020
     ");
 }
 
 #[test]
-fn report_filter_year_month() {
+fn return_without_contributions() {
     let test_context = TestContext::new();
-    test_context.setup_test_content();
+    test_context.setup_empty_test_content();
 
-    let args = vec!["report", "--filter", "2024-10"];
+    let args = vec!["return", "--final", "100"];
     assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
     success: true
     exit_code: 0
     ----- stdout -----
-                           2024-10-01:  -200.00
-                           2024-10-02: 3 000.42
-    Total amount for filter '2024-10': 2 800.42
+    Contributions:   0.00
+    Final balance: 100.00
+             Gain: 100.00
+           Return:    n/a
 
     ----- stderr -----
     ");
 }
 
 #[test]
-fn report_filter_year_month_no_entries_error() {
-    let test_context = TestContext::new();
-    test_context.setup_test_content();
-
-    let args = vec!["report", "--filter", "2020-01"];
-    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+fn new_entry_invalid_tz() {
+    let args = vec![
+        "new-entry",
+        "--tz",
+        "Europe/Atlantis",
+        "--amount",
+        "5",
+        "test.csv",
+    ];
+    assert_cmd_snapshot!(Cli::with_args(args).cmd(), @r"
     success: false
-    exit_code: 1
+    exit_code: 2
     ----- stdout -----
 
     ----- stderr -----
-    Error: No entries matching filter: 2020-01
+    error: invalid value 'Europe/Atlantis' for '--tz <TIMEZONE>': unknown timezone 'Europe/Atlantis', expected an IANA name like America/New_York or UTC
+
+    For more information, try '--help'.
     ");
 }
 
 #[test]
-fn report_no_file_error() {
+fn test_version() {
+    let args = vec!["--version"];
+    assert_cmd_snapshot!(Cli::with_args(args).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    mfinance 0.1.0
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_config_command_prints_effective_config() {
     let mut test_context = TestContext::new();
     test_context.setup_insta_filter();
+    test_context.setup_global_config(
+        r#"
+        [formatting]
+        currency_symbol = "€"
+        decimal_separator = ","
+        "#,
+    );
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        currency_position = "Suffix"
 
-    let args = vec!["report"];
-    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
-    success: false
-    exit_code: 1
+        [accounts]
+        "expenses.csv" = "expense"
+        "#,
+    );
+
+    let args = vec!["config"];
+    assert_cmd_snapshot!(
+        Cli::with_args(args)
+            .global_config_dir(test_context.path())
+            .path(test_context.path())
+            .cmd(),
+        @r#"
+    success: true
+    exit_code: 0
     ----- stdout -----
+    Global config: [TEMP_DIR]/config.toml
+    Local config: [TEMP_DIR]/mfinance.toml
+
+    empty_result = "error"
+
+    [formatting]
+    currency_symbol = "€"
+    currency_position = "Suffix"
+    thousands_separator = "\u00A0"
+    decimal_separator = ","
+    # pad_integer_digits is not set
+    reconcile_rounding = false
+
+    [accounts]
+    "expenses.csv" = "expense"
+
+    [keys]
+    next = "j"
+    prev = "k"
+    # focus_next is not set
+    add = "n"
+    edit = "e"
+    info = "i"
+    view = "v"
+    absolute = "x"
+    goto = "g"
+    quit = "q"
+
+    [tui]
+    show_directory = false
+    zebra_stripes = false
+    absolute_amounts = false
+    dim_fractions = false
+    view = "panes"
+
+    [layout]
+    files = 1
+    years = 1
+    entries = 1
+
+    [backup]
+    enabled = false
+    overwrite = true
 
     ----- stderr -----
-    Error: I/O error: Failed to access file: [TEMP_DIR]/test.csv
-    caused by: No such file or directory (os error 2)
-    ");
+    "#
+    );
 }
 
 #[test]
-fn report_no_entries_error() {
-    let test_context = TestContext::new();
-    test_context.setup_empty_test_content();
-
-    let args = vec!["report"];
-    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
-    success: false
-    exit_code: 1
+fn test_config_command_without_config_files() {
+    let args = vec!["config"];
+    assert_cmd_snapshot!(Cli::with_args(args).cmd(), @r#"
+    success: true
+    exit_code: 0
     ----- stdout -----
+    Global config: none
+    Local config: none
+
+    empty_result = "error"
+
+    [formatting]
+    # currency_symbol is not set
+    # currency_position is not set
+    thousands_separator = "\u00A0"
+    decimal_separator = "."
+    # pad_integer_digits is not set
+    reconcile_rounding = false
+
+    [accounts]
+
+    [keys]
+    next = "j"
+    prev = "k"
+    # focus_next is not set
+    add = "n"
+    edit = "e"
+    info = "i"
+    view = "v"
+    absolute = "x"
+    goto = "g"
+    quit = "q"
+
+    [tui]
+    show_directory = false
+    zebra_stripes = false
+    absolute_amounts = false
+    dim_fractions = false
+    view = "panes"
+
+    [layout]
+    files = 1
+    years = 1
+    entries = 1
+
+    [backup]
+    enabled = false
+    overwrite = true
 
     ----- stderr -----
-    Error: No entries found
-    ");
+    "#);
 }
 
 #[test]
-fn sort() {
+fn test_config_show() {
     let test_context = TestContext::new();
-    test_context.setup_test_content();
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        currency_symbol = "$"
+        decimal_separator = ","
+        "#,
+    );
 
-    let args = vec!["sort"];
-    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    let args = vec!["config", "show"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r#"
     success: true
     exit_code: 0
     ----- stdout -----
+    empty_result = "error"
+
+    [formatting]
+    currency_symbol = "$"
+    # currency_position is not set
+    thousands_separator = "\u00A0"
+    decimal_separator = ","
+    # pad_integer_digits is not set
+    reconcile_rounding = false
+
+    [accounts]
+
+    [keys]
+    next = "j"
+    prev = "k"
+    # focus_next is not set
+    add = "n"
+    edit = "e"
+    info = "i"
+    view = "v"
+    absolute = "x"
+    goto = "g"
+    quit = "q"
+
+    [tui]
+    show_directory = false
+    zebra_stripes = false
+    absolute_amounts = false
+    dim_fractions = false
+    view = "panes"
+
+    [layout]
+    files = 1
+    years = 1
+    entries = 1
+
+    [backup]
+    enabled = false
+    overwrite = true
 
     ----- stderr -----
-    ");
+    "#);
+}
 
-    assert_snapshot!(test_context.content(), @r"
-    date;amount
-    2024-09-11;700
-    2024-10-01;-200
-    2024-10-02;3000.42
-    2025-01-01;10
+#[test]
+fn test_config_path() {
+    let mut test_context = TestContext::new();
+    test_context.setup_insta_filter();
+    test_context.setup_data_config("");
+
+    let args = vec!["config", "path"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Global config: none
+    Local config: [TEMP_DIR]/mfinance.toml
+
+    ----- stderr -----
     ");
 }
 
 #[test]
-fn test_version() {
-    let args = vec!["--version"];
-    assert_cmd_snapshot!(Cli::with_args(args).cmd(), @r"
+fn test_config_sample() {
+    let test_context = TestContext::new();
+
+    let args = vec!["config", "sample"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r"
     success: true
     exit_code: 0
     ----- stdout -----
-    mfinance 0.1.0
+    1 234 567.89
+
+    ----- stderr -----
+    ");
+
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        currency_symbol = "€"
+        currency_position = "Suffix"
+        thousands_separator = "."
+        decimal_separator = ","
+        "#,
+    );
+    let args = vec!["config", "sample"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    1.234.567,89€
 
     ----- stderr -----
     ");
@@ -313,6 +3163,86 @@ fn test_config_with_global_and_data() {
     ");
 }
 
+#[test]
+fn test_config_precedence() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    test_context.setup_global_config(
+        r#"
+        [formatting]
+        decimal_separator = ","
+        "#,
+    );
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        decimal_separator = "/"
+        "#,
+    );
+
+    let args = vec!["report", "--filter", "2025"];
+    let cli = Cli::with_args(args)
+        .global_config_dir(test_context.path())
+        .path(test_context.content_path());
+    assert_cmd_snapshot!(cli.cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2025-01-01: 10/00
+    Total amount for filter '2025': 10/00
+
+    ----- stderr -----
+    ");
+
+    let args = vec![
+        "report",
+        "--filter",
+        "2025",
+        "--config-precedence",
+        "global",
+    ];
+    let cli = Cli::with_args(args)
+        .global_config_dir(test_context.path())
+        .path(test_context.content_path());
+    assert_cmd_snapshot!(cli.cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2025-01-01: 10,00
+    Total amount for filter '2025': 10,00
+
+    ----- stderr -----
+    ");
+}
+
+#[test]
+fn test_config_with_same_separators_falls_back_to_defaults() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        thousands_separator = "."
+        decimal_separator = "."
+        "#,
+    );
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-09-11:   700.00
+      2024-10-01:  -200.00
+      2024-10-02: 3 000.42
+      2025-01-01:    10.00
+    Total amount: 3 510.42
+
+    ----- stderr -----
+    Warning: Thousands and decimal separators are both '.', using the default separators
+    ");
+}
+
 #[test]
 fn test_config_with_only_global() {
     let test_context = TestContext::new();
@@ -371,6 +3301,62 @@ fn test_config_with_only_data() {
     ");
 }
 
+#[test]
+fn test_config_warning_on_conflicting_keys() {
+    let test_context = TestContext::new();
+    test_context.setup_test_content();
+    test_context.setup_data_config(
+        r#"
+        [keys]
+        next = "q"
+        "#,
+    );
+
+    let args = vec!["report", "--filter", "2025"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+                        2025-01-01: 10.00
+    Total amount for filter '2025': 10.00
+
+    ----- stderr -----
+    Warning: Key 'q' is bound to multiple actions: next, quit
+    ");
+}
+
+#[test]
+fn test_report_aligns_decimal_separators_of_mixed_signs() {
+    let test_context = TestContext::new();
+    fs::write(
+        test_context.content_path(),
+        "date;amount\n2024-10-01;-200\n2024-10-02;3000.42\n2024-10-03;-3000.42\n2024-10-04;0.5\n",
+    )
+    .expect("write test.csv");
+    test_context.setup_data_config(
+        r#"
+        [formatting]
+        currency_symbol = "€"
+        currency_position = "Prefix"
+        decimal_separator = ","
+        "#,
+    );
+
+    let args = vec!["report"];
+    assert_cmd_snapshot!(Cli::with_args(args).path(test_context.content_path()).cmd(), @r"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+      2024-10-01:   €-200,00
+      2024-10-02:  €3 000,42
+      2024-10-03: €-3 000,42
+      2024-10-04:      €0,50
+    Total amount:   €-199,50
+
+    ----- stderr -----
+    ");
+}
+
 struct Cli {
     command: Command,
 }
@@ -394,6 +3380,19 @@ impl Cli {
         self
     }
 
+    fn today(mut self, date: &str) -> Self {
+        self.command.env("MFINANCE_TODAY", date);
+        self
+    }
+
+    fn numeric_locale(mut self, locale: &str) -> Self {
+        self.command
+            .env_remove("LC_ALL")
+            .env_remove("LANG")
+            .env("LC_NUMERIC", locale);
+        self
+    }
+
     fn cmd(self) -> Command {
         self.command
     }
@@ -1,14 +1,22 @@
+use chrono::NaiveDate;
 use insta::assert_snapshot;
-use mfinance::{config::Config, tui::run_tui_loop};
+use mfinance::{
+    clock::FixedClock,
+    config::{AccountKind, Config, TuiView},
+    csv_files_at, lock_file,
+    tui::run_tui_loop,
+};
 use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{Terminal, backend::TestBackend};
 use std::{fs, path::PathBuf};
 use temp_dir::TempDir;
 
 struct TuiTestFixture {
-    #[allow(dead_code)] // Used to keep temp directory alive
     tempdir: TempDir,
     files: Vec<PathBuf>,
+    config: Config,
+    select: Option<String>,
+    today: NaiveDate,
     is_with_styles: bool,
 }
 
@@ -51,14 +59,13 @@ impl TuiTestFixture {
         TuiTestFixture {
             tempdir,
             files,
+            config: Config::default(),
+            select: None,
+            today: NaiveDate::from_ymd_opt(2025, 2, 14).unwrap(),
             is_with_styles: false,
         }
     }
 
-    fn config() -> Config {
-        Config::default()
-    }
-
     /// Run TUI with events and return final buffer content
     fn run_with_events(&self, events: impl IntoIterator<Item = Vec<Event>>) -> String {
         let files = self.files.clone();
@@ -67,7 +74,9 @@ impl TuiTestFixture {
 
         run_tui_loop(
             files,
-            Self::config(),
+            self.config.clone(),
+            self.select.as_deref(),
+            Box::new(FixedClock(self.today)),
             &mut terminal,
             events.into_iter().flatten(),
         )
@@ -131,6 +140,10 @@ fn type_text(s: &str) -> Vec<Event> {
     s.chars().map(|ch| key_event(KeyCode::Char(ch))).collect()
 }
 
+fn paste(s: &str) -> Vec<Event> {
+    vec![Event::Paste(s.to_string())]
+}
+
 fn repeat(events: Vec<Event>, n_times: usize) -> Vec<Event> {
     let mut result: Vec<Event> = Vec::with_capacity(events.len() * n_times);
     for _ in 0..n_times {
@@ -186,10 +199,729 @@ fn test_up_or_k() {
     let output = fixture.run_with_events(vec![type_text("k"), press_up()]);
 
     assert_snapshot!(output, @r#"
-    "╔ Files ════════════════════╗┌ savings.csv ─────────────┐┌ 2024 ─────────────────────┐"
-    "║ expenses.csv              ║│▎2024            1 500.00 ││ June 15            500.00 │"
-    "║ income.csv                ║│                          ││▎December 31      1 000.00 │"
-    "║▌savings.csv      1 500.00 ║│                          ││                           │"
+    "╔ Files ════════════════════╗┌ savings.csv ─────────────┐┌ 2024 ─────────────────────┐"
+    "║ expenses.csv              ║│▎2024            1 500.00 ││ June 15            500.00 │"
+    "║ income.csv                ║│                          ││▎December 31      1 000.00 │"
+    "║▌savings.csv      1 500.00 ║│                          ││                           │"
+    "║ hustle.csv                ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_remapped_navigation_keys() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.config.keys.next = 's';
+    fixture.config.keys.prev = 'w';
+
+    // `j` is no longer bound, `s` moves down twice and `w` moves back up once.
+    let output = fixture.run_with_events(vec![type_text("jssw")]);
+
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ income.csv ──────────────┐┌ 2025 ─────────────────────┐"
+    "║ expenses.csv              ║│ 2024            6 000.00 ││▎January 1        2 000.00 │"
+    "║▌income.csv       8 000.00 ║│▎2025            2 000.00 ││                           │"
+    "║ savings.csv               ║│                          ││                           │"
+    "║ hustle.csv                ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(s)/↑(w): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_long_file_name_is_truncated() {
+    let mut fixture = TuiTestFixture::new();
+    let long_file_path = fixture
+        .tempdir
+        .child("a-very-long-file-name-for-household-expenses.csv");
+    fs::write(&long_file_path, "date;amount\n2024-01-15;-1050.25\n")
+        .expect("write long file name csv");
+    fixture.files.insert(0, long_file_path);
+
+    let output = fixture.run_with_events(vec![]);
+
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ a-very-long-file-name-for┐┌ 2024 ─────────────────────┐"
+    "║▌a-very-long-fi… -1 050.25 ║│▎2024           -1 050.25 ││▎January 15      -1 050.25 │"
+    "║ expenses.csv              ║│                          ││                           │"
+    "║ income.csv                ║│                          ││                           │"
+    "║ savings.csv               ║│                          ││                           │"
+    "║ hustle.csv                ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_today_and_current_month_entries_are_highlighted() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.is_with_styles = true;
+    let recent_path = fixture.tempdir.child("recent.csv");
+    fs::write(
+        &recent_path,
+        "date;amount\n2025-01-14;-1.00\n2025-02-01;-2.00\n2025-02-14;-3.00\n",
+    )
+    .expect("write recent.csv");
+    fixture.files.insert(0, recent_path);
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    Buffer {
+        area: Rect { x: 0, y: 0, width: 86, height: 20 },
+        content: [
+            "╔ Files ════════════════════╗┌ recent.csv ──────────────┐┌ 2025 ─────────────────────┐",
+            "║▌recent.csv          -6.00 ║│▎2025               -6.00 ││ January 14          -1.00 │",
+            "║ expenses.csv              ║│                          ││ February 1          -2.00 │",
+            "║ income.csv                ║│                          ││▎February 14         -3.00 │",
+            "║ savings.csv               ║│                          ││                           │",
+            "║ hustle.csv                ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘",
+            "┌────────────────────────────────────────────────────────────────────────────────────┐",
+            "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │",
+            "└────────────────────────────────────────────────────────────────────────────────────┘",
+        ],
+        styles: [
+            x: 0, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 1, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: BOLD,
+            x: 8, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 1, y: 1, fg: Green, bg: Rgb(26, 30, 36), underline: Reset, modifier: NONE,
+            x: 2, y: 1, fg: Reset, bg: Rgb(26, 30, 36), underline: Reset, modifier: NONE,
+            x: 28, y: 1, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 30, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 56, y: 1, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 59, y: 2, fg: Cyan, bg: Reset, underline: Reset, modifier: NONE,
+            x: 69, y: 2, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 58, y: 3, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 59, y: 3, fg: Yellow, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 70, y: 3, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 85, y: 3, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+        ]
+    }
+    "#);
+}
+
+#[test]
+fn test_tree_view_expands_year() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.config.tui.view = TuiView::Tree;
+
+    // Expand 2024, select its second entry, then collapse it again
+    let output = fixture.run_with_events(vec![
+        press_tab(),
+        press_up(),
+        press_enter(),
+        repeat(press_down(), 2),
+    ]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐╔ expenses.csv ═════════════════════════════════════════╗"
+    "│▎expenses.csv      -251.50 │║ 2024                                          -175.75 ║"
+    "│ income.csv                │║   January 15                                   -50.25 ║"
+    "│ savings.csv               │║▌  February 20                                 -100.00 ║"
+    "│ hustle.csv                │║   March 10                                     -25.50 ║"
+    "│                           │║ 2025                                           -75.75 ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "└───────────────────────────┘╚═══════════════════════════════════════════════════════╝"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+
+    let output = fixture.run_with_events(vec![
+        press_tab(),
+        press_up(),
+        press_enter(),
+        repeat(press_down(), 2),
+        press_enter(),
+    ]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐╔ expenses.csv ═════════════════════════════════════════╗"
+    "│▎expenses.csv      -251.50 │║▌2024                                          -175.75 ║"
+    "│ income.csv                │║ 2025                                           -75.75 ║"
+    "│ savings.csv               │║                                                       ║"
+    "│ hustle.csv                │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "│                           │║                                                       ║"
+    "└───────────────────────────┘╚═══════════════════════════════════════════════════════╝"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_layout_ratios() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.config.layout.files = 2;
+    fixture.config.layout.years = 2;
+    fixture.config.layout.entries = 3;
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════╗┌ expenses.csv ────────┐┌ 2025 ─────────────────────────────┐"
+    "║▌expenses.csv  -251.50 ║│ 2024         -175.75 ││▎January 5                  -75.75 │"
+    "║ income.csv            ║│▎2025          -75.75 ││                                   │"
+    "║ savings.csv           ║│                      ││                                   │"
+    "║ hustle.csv            ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "║                       ║│                      ││                                   │"
+    "╚═══════════════════════╝└──────────────────────┘└───────────────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_dim_fractions() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.is_with_styles = true;
+    fixture.config.tui.dim_fractions = true;
+    let dimmed_path = fixture.tempdir.child("dimmed.csv");
+    fs::write(&dimmed_path, "date;amount\n2023-03-01;-1.25\n").expect("write dimmed.csv");
+    fixture.files.insert(0, dimmed_path);
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    Buffer {
+        area: Rect { x: 0, y: 0, width: 86, height: 20 },
+        content: [
+            "╔ Files ════════════════════╗┌ dimmed.csv ──────────────┐┌ 2023 ─────────────────────┐",
+            "║▌dimmed.csv          -1.25 ║│▎2023               -1.25 ││▎March 1             -1.25 │",
+            "║ expenses.csv              ║│                          ││                           │",
+            "║ income.csv                ║│                          ││                           │",
+            "║ savings.csv               ║│                          ││                           │",
+            "║ hustle.csv                ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘",
+            "┌────────────────────────────────────────────────────────────────────────────────────┐",
+            "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │",
+            "└────────────────────────────────────────────────────────────────────────────────────┘",
+        ],
+        styles: [
+            x: 0, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 1, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: BOLD,
+            x: 8, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 1, y: 1, fg: Green, bg: Rgb(26, 30, 36), underline: Reset, modifier: NONE,
+            x: 2, y: 1, fg: Reset, bg: Rgb(26, 30, 36), underline: Reset, modifier: NONE,
+            x: 24, y: 1, fg: Reset, bg: Rgb(26, 30, 36), underline: Reset, modifier: DIM,
+            x: 27, y: 1, fg: Reset, bg: Rgb(26, 30, 36), underline: Reset, modifier: NONE,
+            x: 28, y: 1, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 30, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 52, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: DIM,
+            x: 55, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 56, y: 1, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 58, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 81, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: DIM,
+            x: 84, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 85, y: 1, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+        ]
+    }
+    "#);
+}
+
+#[test]
+fn test_single_file_path() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.files = csv_files_at(&fixture.tempdir.child("savings.csv")).expect("csv files");
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ savings.csv ─────────────┐┌ 2024 ─────────────────────┐"
+    "║▌savings.csv      1 500.00 ║│▎2024            1 500.00 ││ June 15            500.00 │"
+    "║                           ║│                          ││▎December 31      1 000.00 │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_zebra_stripes() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.is_with_styles = true;
+    fixture.config.tui.zebra_stripes = true;
+    let striped_path = fixture.tempdir.child("striped.csv");
+    fs::write(
+        &striped_path,
+        "date;amount\n2023-03-01;-1.00\n2023-03-02;-2.00\n2023-03-03;-3.00\n2023-03-04;-4.00\n2023-03-05;-5.00\n",
+    )
+    .expect("write striped.csv");
+    fixture.files.insert(0, striped_path);
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    Buffer {
+        area: Rect { x: 0, y: 0, width: 86, height: 20 },
+        content: [
+            "╔ Files ════════════════════╗┌ striped.csv ─────────────┐┌ 2023 ─────────────────────┐",
+            "║▌striped.csv        -15.00 ║│▎2023              -15.00 ││ March 1             -1.00 │",
+            "║ expenses.csv              ║│                          ││ March 2             -2.00 │",
+            "║ income.csv                ║│                          ││ March 3             -3.00 │",
+            "║ savings.csv               ║│                          ││ March 4             -4.00 │",
+            "║ hustle.csv                ║│                          ││▎March 5             -5.00 │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "║                           ║│                          ││                           │",
+            "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘",
+            "┌────────────────────────────────────────────────────────────────────────────────────┐",
+            "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │",
+            "└────────────────────────────────────────────────────────────────────────────────────┘",
+        ],
+        styles: [
+            x: 0, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 1, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: BOLD,
+            x: 8, y: 0, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 1, y: 1, fg: Green, bg: Rgb(26, 30, 36), underline: Reset, modifier: NONE,
+            x: 2, y: 1, fg: Reset, bg: Rgb(26, 30, 36), underline: Reset, modifier: NONE,
+            x: 28, y: 1, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 30, y: 1, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 56, y: 1, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 58, y: 2, fg: Reset, bg: Rgb(22, 22, 22), underline: Reset, modifier: NONE,
+            x: 85, y: 2, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 58, y: 4, fg: Reset, bg: Rgb(22, 22, 22), underline: Reset, modifier: NONE,
+            x: 85, y: 4, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+            x: 58, y: 5, fg: Reset, bg: Rgb(35, 39, 48), underline: Reset, modifier: NONE,
+            x: 85, y: 5, fg: Reset, bg: Reset, underline: Reset, modifier: NONE,
+        ]
+    }
+    "#);
+}
+
+#[test]
+fn test_load_error_popup() {
+    let mut fixture = TuiTestFixture::new();
+    let broken_path = fixture.tempdir.child("broken.csv");
+    fs::write(&broken_path, "date;amount\n2025-01-01;abc\n").expect("write broken.csv");
+    fixture.files.insert(0, broken_path);
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ broken.csv ──────────────┐┌  ─────────────────────────┐"
+    "│▎broken.csv                ││                          ││                           │"
+    "│ expenses.csv              ││                          ││                           │"
+    "│ income.csv                ││                          ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                ╔ Error ═══════════════════════════════════════════╗                │"
+    "│                ║ CSV error: CSV deserialize error: record 1       ║                │"
+    "│                ║ (line: 2, byte: 12): invalid value: string       ║                │"
+    "│                ║ "abc", expected a Decimal type representing a    ║                │"
+    "│                ║ fixed-point number                               ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│q/Enter: Close                                                                      │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_load_error_popup_closes() {
+    let mut fixture = TuiTestFixture::new();
+    let broken_path = fixture.tempdir.child("broken.csv");
+    fs::write(&broken_path, "date;amount\n2025-01-01;abc\n").expect("write broken.csv");
+    fixture.files.insert(0, broken_path);
+
+    let output = fixture.run_with_events(vec![press_enter(), press_down()]);
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "║ broken.csv                ║│ 2024             -175.75 ││▎January 5          -75.75 │"
+    "║▌expenses.csv      -251.50 ║│▎2025              -75.75 ││                           │"
+    "║ income.csv                ║│                          ││                           │"
+    "║ savings.csv               ║│                          ││                           │"
+    "║ hustle.csv                ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_paste_into_popup_fields() {
+    let fixture = TuiTestFixture::new();
+
+    let output = fixture.run_with_events(vec![
+        press_new_entry(),
+        paste(" $1,234.50\n"),
+        press_tab(),
+        repeat(press_backspace(), 10),
+        paste("2024-12-15T10:00:00"),
+    ]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║▌Date    2024-12-15                               ║                │"
+    "│                ║ Amount  1234.50                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_paste_non_ascii_into_date_field() {
+    let fixture = TuiTestFixture::new();
+
+    let output = fixture.run_with_events(vec![
+        press_new_entry(),
+        press_tab(),
+        repeat(press_backspace(), 10),
+        paste("2024-09-1é…"),
+    ]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║▌Date    2024-09-1é                               ║                │"
+    "│                ║ Amount                                           ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_long_popup_value_scrolls() {
+    let fixture = TuiTestFixture::new();
+
+    let output = fixture.run_with_events(vec![
+        press_new_entry(),
+        type_text("1234567890123456789012345678901234567890123456789"),
+    ]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-02-14                               ║                │"
+    "│                ║▌Amount  0123456789012345678901234567890123456789 ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_popup_evaluates_amount_expression() {
+    let fixture = TuiTestFixture::new();
+
+    let output = fixture.run_with_events(vec![press_new_entry(), type_text("12 + 3")]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-02-14                               ║                │"
+    "│                ║▌Amount  12 + 3                            = 15.00║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_toggle_absolute_amounts() {
+    let fixture = TuiTestFixture::new();
+
+    let toggle = || vec![key_event(KeyCode::Char('x'))];
+    let output = fixture.run_with_events(vec![toggle()]);
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "║▌expenses.csv      -251.50 ║│ 2024             -175.75 ││▎January 5           75.75 │"
+    "║ income.csv                ║│▎2025              -75.75 ││                           │"
+    "║ savings.csv               ║│                          ││                           │"
+    "║ hustle.csv                ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+
+    let output = fixture.run_with_events(vec![toggle(), toggle()]);
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "║▌expenses.csv      -251.50 ║│ 2024             -175.75 ││▎January 5          -75.75 │"
+    "║ income.csv                ║│▎2025              -75.75 ││                           │"
+    "║ savings.csv               ║│                          ││                           │"
+    "║ hustle.csv                ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_show_directory() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.config.tui.show_directory = true;
+
+    let output = fixture.run_with_events(vec![]);
+
+    let mut settings = insta::Settings::clone_current();
+    // The temporary path varies in length, so the padding after it is dropped too
+    settings.add_filter(r#"/\S*mfinance-tui-test-\S* *""#, r#"[DIR]""#);
+    settings.bind(|| {
+        assert_snapshot!(output, @r#"
+        " [DIR]"
+        "╔ Files ════════════════════╗┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+        "║▌expenses.csv      -251.50 ║│ 2024             -175.75 ││▎January 5          -75.75 │"
+        "║ income.csv                ║│▎2025              -75.75 ││                           │"
+        "║ savings.csv               ║│                          ││                           │"
+        "║ hustle.csv                ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "║                           ║│                          ││                           │"
+        "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+        "┌────────────────────────────────────────────────────────────────────────────────────┐"
+        "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+        "└────────────────────────────────────────────────────────────────────────────────────┘"
+        "#);
+    });
+}
+
+#[test]
+fn test_select_fuzzy_matching_file() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.select = Some("SVN".to_string());
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ savings.csv ─────────────┐┌ 2024 ─────────────────────┐"
+    "║ expenses.csv              ║│▎2024            1 500.00 ││ June 15            500.00 │"
+    "║ income.csv                ║│                          ││▎December 31      1 000.00 │"
+    "║▌savings.csv      1 500.00 ║│                          ││                           │"
+    "║ hustle.csv                ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "║                           ║│                          ││                           │"
+    "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_select_prefers_exact_name() {
+    let mut fixture = TuiTestFixture::new();
+    let old_income_path = fixture.tempdir.child("old-income.csv");
+    fs::write(&old_income_path, "date;amount\n2020-01-01;1.00\n").expect("write old-income.csv");
+    fixture.files.insert(0, old_income_path);
+    fixture.select = Some("income.csv".to_string());
+
+    let output = fixture.run_with_events(vec![]);
+    assert_snapshot!(output, @r#"
+    "╔ Files ════════════════════╗┌ income.csv ──────────────┐┌ 2025 ─────────────────────┐"
+    "║ old-income.csv            ║│ 2024            6 000.00 ││▎January 1        2 000.00 │"
+    "║ expenses.csv              ║│▎2025            2 000.00 ││                           │"
+    "║▌income.csv       8 000.00 ║│                          ││                           │"
+    "║ savings.csv               ║│                          ││                           │"
     "║ hustle.csv                ║│                          ││                           │"
     "║                           ║│                          ││                           │"
     "║                           ║│                          ││                           │"
@@ -201,7 +933,6 @@ fn test_up_or_k() {
     "║                           ║│                          ││                           │"
     "║                           ║│                          ││                           │"
     "║                           ║│                          ││                           │"
-    "║                           ║│                          ││                           │"
     "╚═══════════════════════════╝└──────────────────────────┘└───────────────────────────┘"
     "┌────────────────────────────────────────────────────────────────────────────────────┐"
     "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
@@ -209,6 +940,15 @@ fn test_up_or_k() {
     "#);
 }
 
+#[test]
+fn test_select_without_match_falls_back_to_first_file() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.select = Some("xyz".to_string());
+
+    let output = fixture.run_with_events(vec![]);
+    assert_eq!(output, TuiTestFixture::new().run_with_events(vec![]));
+}
+
 #[test]
 fn test_focus_on_years() {
     let fixture = TuiTestFixture::new();
@@ -371,33 +1111,251 @@ fn test_add_entry_popup_open() {
 
     let output = fixture.run_with_events(vec![press_new_entry()]);
 
-    let mut settings = insta::Settings::clone_current();
-    let current_date = chrono::Local::now().date_naive().to_string();
-    settings.add_filter(&current_date, "0000-00-00");
-    settings.bind(|| {
-        assert_snapshot!(output, @r#"
-        "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
-        "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
-        "│ income.csv                ││▎2025              -75.75 ││                           │"
-        "│ savings.csv               ││                          ││                           │"
-        "│ hustle.csv                ││                          ││                           │"
-        "│                           ││                          ││                           │"
-        "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
-        "│                ║ File    expenses.csv                             ║                │"
-        "│                ║                                                  ║                │"
-        "│                ║ Date    0000-00-00                               ║                │"
-        "│                ║▌Amount                                           ║                │"
-        "│                ║                                                  ║                │"
-        "│                ║                                                  ║                │"
-        "│                ╚══════════════════════════════════════════════════╝                │"
-        "│                           ││                          ││                           │"
-        "│                           ││                          ││                           │"
-        "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
-        "┌────────────────────────────────────────────────────────────────────────────────────┐"
-        "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
-        "└────────────────────────────────────────────────────────────────────────────────────┘"
-        "#);
-    });
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-02-14                               ║                │"
+    "│                ║▌Amount                                           ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_add_entry_popup_prefills_minus_for_expense_account() {
+    let mut fixture = TuiTestFixture::new();
+    fixture
+        .config
+        .accounts
+        .insert(String::from("expenses.csv"), AccountKind::Expense);
+
+    let output = fixture.run_with_events(vec![press_new_entry()]);
+
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-02-14                               ║                │"
+    "│                ║▌Amount  -                                        ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_expense_account_minus_applies_to_whole_expression() {
+    let mut fixture = TuiTestFixture::new();
+    fixture
+        .config
+        .accounts
+        .insert(String::from("expenses.csv"), AccountKind::Expense);
+
+    let output = fixture.run_with_events(vec![press_new_entry(), type_text("12 + 3")]);
+
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-02-14                               ║                │"
+    "│                ║▌Amount  -12 + 3                          = -15.00║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_expense_account_edit_keeps_stored_sign() {
+    let mut fixture = TuiTestFixture::new();
+    fixture
+        .config
+        .accounts
+        .insert(String::from("expenses.csv"), AccountKind::Expense);
+
+    let output = fixture.run_with_events(vec![
+        repeat(press_tab(), 2),
+        press_edit_entry(),
+        press_tab(),
+        type_text(" + 3"),
+    ]);
+
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Edit Entry ══════════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-01-05                               ║                │"
+    "│                ║▌Amount  -75.75 + 3                       = -72.75║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_info_popup_shows_selected_entry() {
+    let fixture = TuiTestFixture::new();
+
+    // Typing has no effect on the read-only details
+    let output = fixture.run_with_events(vec![
+        repeat(press_tab(), 2),
+        type_text("i"),
+        type_text("12"),
+    ]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Entry ═══════════════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-01-05                               ║                │"
+    "│                ║ Amount  -75.75                                   ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│q/Enter: Close                                                                      │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_goto_date_selects_closest_following_entry() {
+    let fixture = TuiTestFixture::new();
+
+    let output =
+        fixture.run_with_events(vec![type_text("g"), type_text("2024-02-21"), press_enter()]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐╔ 2024 ═════════════════════╗"
+    "│▎expenses.csv      -251.50 ││▎2024             -175.75 │║ January 15         -50.25 ║"
+    "│ income.csv                ││ 2025              -75.75 │║ February 20       -100.00 ║"
+    "│ savings.csv               ││                          │║▌March 10           -25.50 ║"
+    "│ hustle.csv                ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "└───────────────────────────┘└──────────────────────────┘╚═══════════════════════════╝"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_goto_date_ignores_tab() {
+    let fixture = TuiTestFixture::new();
+
+    let output = fixture.run_with_events(vec![
+        type_text("g"),
+        press_tab(),
+        type_text("2024-02-21"),
+        press_enter(),
+    ]);
+    let without_tab =
+        fixture.run_with_events(vec![type_text("g"), type_text("2024-02-21"), press_enter()]);
+    assert_eq!(output, without_tab);
+}
+
+#[test]
+fn test_goto_date_without_following_entries() {
+    let fixture = TuiTestFixture::new();
+
+    let output = fixture.run_with_events(vec![type_text("g"), type_text("2025-02"), press_enter()]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Go to Date ══════════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║▌Date    2025-02                                  ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Error: No entries on or after 2025-02-01         ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Enter: Go | q: Cancel                                                               │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
+#[test]
+fn test_info_popup_closes_with_enter() {
+    let fixture = TuiTestFixture::new();
+
+    let output = fixture.run_with_events(vec![type_text("i"), press_enter()]);
+    assert_eq!(output, TuiTestFixture::new().run_with_events(vec![]));
 }
 
 #[test]
@@ -541,6 +1499,77 @@ fn test_add_entry_save_functionality() {
     assert!(final_content.contains("500"), "Should contain new amount");
 }
 
+#[test]
+fn test_add_entry_to_locked_file() {
+    let mut fixture = TuiTestFixture::new();
+    fixture.config.backup.enabled = true;
+    let file_path = fixture.files[0].clone();
+    let initial_content = fs::read_to_string(&file_path).unwrap();
+
+    let _lock = lock_file(&file_path).expect("lock expenses.csv");
+    let output = fixture.run_with_events(vec![press_new_entry(), type_text("-5"), press_enter()]);
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
+    "│▎expenses.csv      -251.50 ││ 2024             -175.75 ││▎January 5          -75.75 │"
+    "│ income.csv                ││▎2025              -75.75 ││                           │"
+    "│ savings.csv               ││                          ││                           │"
+    "│ hustle.csv                ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "│                ╔ Add New Entry ═══════════════════════════════════╗                │"
+    "│                ║ File    expenses.csv                             ║                │"
+    "│                ║                                                  ║                │"
+    "│                ║ Date    2025-02-14                               ║                │"
+    "│                ║▌Amount  -5                                       ║                │"
+    "│                ║ Error: Failed to save: File is locked by another ║                │"
+    "│                ║                                                  ║                │"
+    "│                ╚══════════════════════════════════════════════════╝                │"
+    "│                           ││                          ││                           │"
+    "│                           ││                          ││                           │"
+    "└───────────────────────────┘└──────────────────────────┘└───────────────────────────┘"
+    "┌────────────────────────────────────────────────────────────────────────────────────┐"
+    "│Tab: Switch Field | Enter: Save | q: Cancel                                         │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+    // Neither the file nor its backup is touched while another process holds the lock
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), initial_content);
+    assert!(!file_path.with_extension("csv.bak").exists());
+}
+
+#[test]
+fn test_added_entries_counter() {
+    let fixture = TuiTestFixture::new();
+
+    let add_entry = vec![press_new_entry(), type_text("-5"), press_enter()];
+    let edit_entry = vec![repeat(press_tab(), 2), press_edit_entry(), press_enter()];
+    let output = fixture.run_with_events(
+        [add_entry.clone(), add_entry, edit_entry]
+            .into_iter()
+            .flatten(),
+    );
+    assert_snapshot!(output, @r#"
+    "┌ Files ────────────────────┐┌ expenses.csv ────────────┐╔ 2025 ═════════════════════╗"
+    "│▎expenses.csv      -261.50 ││ 2024             -175.75 │║▌January 5          -75.75 ║"
+    "│ income.csv                ││▎2025              -85.75 │║ February 14         -5.00 ║"
+    "│ savings.csv               ││                          │║ February 14         -5.00 ║"
+    "│ hustle.csv                ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "│                           ││                          │║                           ║"
+    "└───────────────────────────┘└──────────────────────────┘╚═══════════════════════════╝"
+    "┌ Added: 2 this session ─────────────────────────────────────────────────────────────┐"
+    "│↓(j)/↑(k): Navigate | Tab: Focus | n/e: New/Edit Entry | v: View | q: Quit          │"
+    "└────────────────────────────────────────────────────────────────────────────────────┘"
+    "#);
+}
+
 #[test]
 fn test_popup_error_handling() {
     let fixture = TuiTestFixture::new();
@@ -672,7 +1701,7 @@ fn test_debit_credit_view_shows_zero_debit() {
 
     assert_snapshot!(output, @r#"
     "╔ Files ════════════════════╗┌ expenses.csv ────────────┐┌ 2025 ─────────────────────┐"
-    "║▌expenses.csv0.00 | -251.50║│ 2024      0.00 | -175.75 ││▎January 5          -75.75 │"
+    "║▌expenses.… 0.00 | -251.50 ║│ 2024      0.00 | -175.75 ││▎January 5          -75.75 │"
     "║ income.csv                ║│▎2025      0.00 |  -75.75 ││                           │"
     "║ savings.csv               ║│                          ││                           │"
     "║ hustle.csv                ║│                          ││                           │"
@@ -705,7 +1734,7 @@ fn test_debit_credit_view_shows_zero_credit() {
     "╔ Files ════════════════════╗┌ savings.csv ─────────────┐┌ 2024 ─────────────────────┐"
     "║ expenses.csv              ║│▎2024     1 500.00 | 0.00 ││ June 15            500.00 │"
     "║ income.csv                ║│                          ││▎December 31      1 000.00 │"
-    "║▌savings.csv1 500.00 | 0.00║│                          ││                           │"
+    "║▌savings.… 1 500.00 | 0.00 ║│                          ││                           │"
     "║ hustle.csv                ║│                          ││                           │"
     "║                           ║│                          ││                           │"
     "║                           ║│                          ││                           │"